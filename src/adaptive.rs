@@ -0,0 +1,355 @@
+//! A table that hashes with a fast unkeyed function until it sees signs of
+//! hash flooding, then moves to keyed SipHash.
+
+use std::{
+    borrow::Borrow,
+    collections::hash_map::{DefaultHasher, RandomState},
+    hash::{BuildHasher, Hash, Hasher},
+    mem,
+};
+
+use crate::{empty_slots, HashTable, Shadow};
+
+const MULTIPLIER: u64 = 0x517c_c1b7_2722_0a95;
+const DEFAULT_PROBE_LIMIT: usize = 128;
+
+/// The hasher behind [`AdaptiveHashTable`]: an unkeyed multiply-rotate hash
+/// until [`keyed`](Self::keyed) replaces it with a randomly keyed SipHash.
+#[derive(Clone, Default)]
+pub struct AdaptiveState {
+    keyed: Option<RandomState>,
+}
+
+impl AdaptiveState {
+    pub fn keyed() -> Self {
+        Self {
+            keyed: Some(RandomState::new()),
+        }
+    }
+
+    pub fn is_keyed(&self) -> bool {
+        self.keyed.is_some()
+    }
+}
+
+pub struct AdaptiveHasher(Mode);
+
+enum Mode {
+    Fast(u64),
+    Keyed(DefaultHasher),
+}
+
+impl BuildHasher for AdaptiveState {
+    type Hasher = AdaptiveHasher;
+
+    fn build_hasher(&self) -> AdaptiveHasher {
+        match &self.keyed {
+            None => AdaptiveHasher(Mode::Fast(0)),
+            Some(state) => AdaptiveHasher(Mode::Keyed(state.build_hasher())),
+        }
+    }
+}
+
+impl Hasher for AdaptiveHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        match &mut self.0 {
+            Mode::Fast(hash) => {
+                for chunk in bytes.chunks(8) {
+                    let mut word = [0; 8];
+                    word[..chunk.len()].copy_from_slice(chunk);
+                    *hash = mix(*hash, u64::from_le_bytes(word));
+                }
+            }
+            Mode::Keyed(hasher) => hasher.write(bytes),
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        match &mut self.0 {
+            Mode::Fast(hash) => *hash = mix(*hash, n),
+            Mode::Keyed(hasher) => hasher.write_u64(n),
+        }
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        match &self.0 {
+            // The product's high bits depend on every input bit; rotate them
+            // down to where slot indexing looks.
+            Mode::Fast(hash) => hash.rotate_left(26),
+            Mode::Keyed(hasher) => hasher.finish(),
+        }
+    }
+}
+
+fn mix(hash: u64, word: u64) -> u64 {
+    (hash.rotate_left(5) ^ word).wrapping_mul(MULTIPLIER)
+}
+
+/// A [`HashTable`] that starts out with a fast, predictable hasher and
+/// migrates to keyed SipHash, rehashing every entry, the first time an
+/// insert lands in a run of occupied slots longer than the probe limit.
+///
+/// Watching the run rather than the new key's own probe distance also
+/// catches keys crafted to fill consecutive home slots: each of those lands
+/// at home, but lookups that start early in the run still walk all of it.
+/// Ordinary key sets stay well under the default limit of 128 even at
+/// millions of entries, so they keep the fast hash; keys chosen to collide
+/// under it trip the switch after a bounded amount of extra work. A false
+/// alarm costs one rehash.
+pub struct AdaptiveHashTable<K, V> {
+    table: HashTable<K, V, AdaptiveState>,
+    probe_limit: usize,
+}
+
+impl<K, V> AdaptiveHashTable<K, V>
+where
+    K: Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self::with_probe_limit(DEFAULT_PROBE_LIMIT)
+    }
+
+    /// A table that switches hashers once an insert lands in a run of more
+    /// than `probe_limit` occupied slots.
+    pub fn with_probe_limit(probe_limit: usize) -> Self {
+        Self {
+            table: HashTable::with_hasher(AdaptiveState::default()),
+            probe_limit,
+        }
+    }
+
+    /// Whether the table has switched to keyed hashing.
+    pub fn is_keyed(&self) -> bool {
+        self.table.hasher().is_keyed()
+    }
+
+    pub fn as_table(&self) -> &HashTable<K, V, AdaptiveState> {
+        &self.table
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let table = &mut self.table;
+        let hash = table.make_hash(&key);
+        if let Some(index) = table.find_slot_hashed(hash, |stored| *stored == key) {
+            let bucket = table.slots[index].as_mut().unwrap();
+            return Some(mem::replace(bucket.value_mut(), value));
+        }
+
+        let index = table.insert_new(hash, key, value);
+        if !self.is_keyed() && self.run_exceeds_limit(index) {
+            self.switch_to_keyed();
+        }
+
+        self.table.shadow.verify(&self.table);
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.get(key)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.get_mut(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.contains_key(key)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.remove(key)
+    }
+
+    /// Whether the run of occupied slots around `index` is longer than the
+    /// probe limit. Looks at no more than `probe_limit + 1` slots.
+    fn run_exceeds_limit(&self, index: usize) -> bool {
+        let slots = &self.table.slots;
+        let capacity = slots.len();
+        let mut run = 1;
+        let mut before = index;
+        while run <= self.probe_limit {
+            before = (before + capacity - 1) % capacity;
+            if slots[before].is_none() {
+                break;
+            }
+            run += 1;
+        }
+        let mut after = index;
+        while run <= self.probe_limit {
+            after = (after + 1) % capacity;
+            if slots[after].is_none() {
+                break;
+            }
+            run += 1;
+        }
+        run > self.probe_limit
+    }
+
+    /// Rehashes every entry under a fresh keyed hasher. All keys are hashed
+    /// before anything moves, so a panicking `Hash` leaves the table as it
+    /// was.
+    fn switch_to_keyed(&mut self) {
+        let keyed = AdaptiveState::keyed();
+        let hashes: Vec<u64> = self
+            .table
+            .slots
+            .iter()
+            .flatten()
+            .map(|bucket| keyed.hash_one(&bucket.key))
+            .collect();
+
+        let table = &mut self.table;
+        let slot_count = table.slots.len();
+        let old_slots = mem::replace(&mut table.slots, empty_slots(slot_count));
+        table.size = 0;
+        table.generation += 1;
+        table.shadow = Shadow::new();
+        table.hash_builder = keyed;
+
+        for (mut bucket, hash) in old_slots.into_iter().flatten().zip(hashes) {
+            bucket.hash = hash;
+            table.shadow.insert(&bucket);
+            table.place(bucket);
+        }
+    }
+}
+
+impl<K, V> Default for AdaptiveHashTable<K, V>
+where
+    K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The multiplicative inverse of `MULTIPLIER` modulo 2^64.
+    fn inverse() -> u64 {
+        let mut inverse = MULTIPLIER;
+        for _ in 0..5 {
+            inverse = inverse.wrapping_mul(2u64.wrapping_sub(MULTIPLIER.wrapping_mul(inverse)));
+        }
+        inverse
+    }
+
+    /// `u64` keys that all share home slot 0 under the fast hasher in any
+    /// power-of-two table up to 2^26 slots: their products with the
+    /// multiplier have bits 38 and up clear.
+    fn flooding_keys(count: u64) -> impl Iterator<Item = u64> {
+        let inverse = inverse();
+        (1..=count).map(move |n| n.wrapping_mul(inverse))
+    }
+
+    /// `u64` keys whose fast hashes are `0..count`, so they fill consecutive
+    /// home slots in any power-of-two table of at least `count` slots.
+    fn consecutive_keys(count: u64) -> impl Iterator<Item = u64> {
+        let inverse = inverse();
+        (0..count).map(move |hash| hash.rotate_right(26).wrapping_mul(inverse))
+    }
+
+    #[test]
+    fn test_ordinary_keys_keep_fast_hasher() {
+        let mut numbers = AdaptiveHashTable::new();
+        for n in 0..4_000u64 {
+            numbers.insert(n, n);
+        }
+        let mut names = AdaptiveHashTable::new();
+        for n in 0..2_000 {
+            names.insert(format!("user-{n}"), n);
+        }
+
+        assert!(!numbers.is_keyed());
+        assert!(!names.is_keyed());
+        assert_eq!(numbers.get(&123), Some(&123));
+        assert_eq!(names.get("user-42"), Some(&42));
+    }
+
+    #[test]
+    fn test_flooding_switches_to_keyed_hasher() {
+        let hasher = AdaptiveState::default();
+        let homes: Vec<u64> = flooding_keys(4)
+            .map(|key| hasher.hash_one(key) % 1024)
+            .collect();
+        assert_eq!(homes, [0, 0, 0, 0]);
+
+        let mut table = AdaptiveHashTable::with_probe_limit(16);
+        for (n, key) in flooding_keys(500).enumerate() {
+            table.insert(key, n);
+        }
+
+        assert!(table.is_keyed());
+        assert_eq!(table.len(), 500);
+        for (n, key) in flooding_keys(500).enumerate() {
+            assert_eq!(table.get(&key), Some(&n));
+        }
+
+        table.as_table().check_integrity().unwrap();
+    }
+
+    #[cfg(feature = "entry-metadata")]
+    #[test]
+    fn test_replacing_insert_updates_metadata() {
+        let mut table = AdaptiveHashTable::new();
+        table.insert(1u64, 1);
+        let before = table.as_table().entry_metadata(&1).unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert_eq!(table.insert(1, 2), Some(1));
+        let after = table.as_table().entry_metadata(&1).unwrap();
+        assert_eq!(after.created_at, before.created_at);
+        assert!(after.last_modified > before.last_modified);
+    }
+
+    #[test]
+    fn test_consecutive_homes_switch_to_keyed_hasher() {
+        let hasher = AdaptiveState::default();
+        let homes: Vec<u64> = consecutive_keys(4)
+            .map(|key| hasher.hash_one(key) % 1024)
+            .collect();
+        assert_eq!(homes, [0, 1, 2, 3]);
+
+        let mut table = AdaptiveHashTable::with_probe_limit(16);
+        for (n, key) in consecutive_keys(500).enumerate() {
+            table.insert(key, n);
+        }
+
+        assert!(table.is_keyed());
+        assert_eq!(table.len(), 500);
+        for (n, key) in consecutive_keys(500).enumerate() {
+            assert_eq!(table.get(&key), Some(&n));
+        }
+        table.as_table().check_integrity().unwrap();
+    }
+}
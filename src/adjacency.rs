@@ -0,0 +1,278 @@
+use std::{collections::VecDeque, hash::Hash};
+
+use crate::HashTable;
+
+/// A graph stored as node → neighbor list, with a companion edge table for
+/// O(1) edge lookups and removals.
+///
+/// Each edge records its position in the source node's neighbor list, so
+/// removing it is a swap-remove rather than a scan. Undirected graphs store
+/// every edge in both directions.
+pub struct AdjacencyTable<N: Eq + Hash + Clone> {
+    neighbors: HashTable<N, Vec<N>>,
+    edges: HashTable<(N, N), usize>,
+    edge_count: usize,
+    directed: bool,
+}
+
+impl<N> AdjacencyTable<N>
+where
+    N: Eq + Hash + Clone,
+{
+    pub fn directed() -> Self {
+        Self::with_direction(true)
+    }
+
+    pub fn undirected() -> Self {
+        Self::with_direction(false)
+    }
+
+    fn with_direction(directed: bool) -> Self {
+        Self {
+            neighbors: HashTable::new(),
+            edges: HashTable::new(),
+            edge_count: 0,
+            directed,
+        }
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.neighbors.size
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+
+    pub fn contains_node(&self, node: &N) -> bool {
+        self.neighbors.get(node).is_some()
+    }
+
+    pub fn has_edge(&self, from: &N, to: &N) -> bool {
+        self.edges.get(&(from.clone(), to.clone())).is_some()
+    }
+
+    /// Adds `node` with no edges. Returns `false` if it was already present.
+    pub fn add_node(&mut self, node: N) -> bool {
+        if self.contains_node(&node) {
+            return false;
+        }
+        self.neighbors.insert(node, Vec::new());
+        true
+    }
+
+    /// Adds an edge, creating either endpoint if needed. Returns `false` if
+    /// the edge was already present.
+    pub fn add_edge(&mut self, from: N, to: N) -> bool {
+        self.add_node(from.clone());
+        self.add_node(to.clone());
+
+        if !self.link(&from, &to) {
+            return false;
+        }
+        if !self.directed {
+            self.link(&to, &from);
+        }
+        self.edge_count += 1;
+        true
+    }
+
+    pub fn remove_edge(&mut self, from: &N, to: &N) -> bool {
+        if !self.unlink(from, to) {
+            return false;
+        }
+        if !self.directed {
+            self.unlink(to, from);
+        }
+        self.edge_count -= 1;
+        true
+    }
+
+    /// Removes `node` together with every edge into or out of it.
+    pub fn remove_node(&mut self, node: &N) -> bool {
+        if !self.contains_node(node) {
+            return false;
+        }
+
+        let sources: Vec<N> = if self.directed {
+            self.neighbors
+                .key_set()
+                .iter()
+                .filter(|source| self.has_edge(source, node))
+                .cloned()
+                .collect()
+        } else {
+            self.neighbors(node).cloned().collect()
+        };
+        for source in &sources {
+            self.remove_edge(source, node);
+        }
+
+        let targets: Vec<N> = self.neighbors(node).cloned().collect();
+        for target in &targets {
+            self.remove_edge(node, target);
+        }
+
+        self.neighbors.remove(node);
+        true
+    }
+
+    /// Number of edges leaving `node`, or `None` if it is not in the graph.
+    pub fn degree(&self, node: &N) -> Option<usize> {
+        self.neighbors.get(node).map(Vec::len)
+    }
+
+    /// Nodes reachable over a single edge from `node`, in the order the
+    /// edges were added (until an earlier edge is removed).
+    pub fn neighbors<'a>(&'a self, node: &N) -> impl Iterator<Item = &'a N> + 'a {
+        self.neighbors.get(node).into_iter().flatten()
+    }
+
+    /// Nodes reachable from `start` in breadth-first order, `start` first.
+    pub fn bfs(&self, start: &N) -> Vec<N> {
+        let mut order = Vec::new();
+        let mut seen = HashTable::new();
+        let mut queue = VecDeque::new();
+        if self.contains_node(start) {
+            seen.insert(start.clone(), ());
+            queue.push_back(start.clone());
+        }
+
+        while let Some(node) = queue.pop_front() {
+            for next in self.neighbors(&node) {
+                if seen.get(next).is_none() {
+                    seen.insert(next.clone(), ());
+                    queue.push_back(next.clone());
+                }
+            }
+            order.push(node);
+        }
+        order
+    }
+
+    /// Nodes reachable from `start` in depth-first preorder, `start` first.
+    pub fn dfs(&self, start: &N) -> Vec<N> {
+        let mut order = Vec::new();
+        let mut seen = HashTable::new();
+        let mut stack = Vec::new();
+        if self.contains_node(start) {
+            stack.push(start.clone());
+        }
+
+        while let Some(node) = stack.pop() {
+            if seen.get(&node).is_some() {
+                continue;
+            }
+            seen.insert(node.clone(), ());
+            let mut next: Vec<&N> = self
+                .neighbors(&node)
+                .filter(|next| seen.get(*next).is_none())
+                .collect();
+            next.reverse();
+            stack.extend(next.into_iter().cloned());
+            order.push(node);
+        }
+        order
+    }
+
+    fn link(&mut self, from: &N, to: &N) -> bool {
+        let edge = (from.clone(), to.clone());
+        if self.edges.get(&edge).is_some() {
+            return false;
+        }
+
+        let list = self.list_mut(from);
+        list.push(to.clone());
+        let position = list.len() - 1;
+        self.edges.insert(edge, position);
+        true
+    }
+
+    /// Removes the edge from `from`'s neighbor list by swapping the last
+    /// neighbor into its place, and records the moved edge's new position.
+    fn unlink(&mut self, from: &N, to: &N) -> bool {
+        let Some(position) = self.edges.remove(&(from.clone(), to.clone())) else {
+            return false;
+        };
+
+        let list = self.list_mut(from);
+        list.swap_remove(position);
+        if let Some(moved) = list.get(position).cloned() {
+            let index = self.edges.find_slot(&(from.clone(), moved)).unwrap();
+            self.edges.slots[index].as_mut().unwrap().value = position;
+        }
+        true
+    }
+
+    fn list_mut(&mut self, node: &N) -> &mut Vec<N> {
+        let index = self.neighbors.find_slot(node).unwrap();
+        &mut self.neighbors.slots[index].as_mut().unwrap().value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted<'a>(nodes: impl Iterator<Item = &'a u32>) -> Vec<u32> {
+        let mut nodes: Vec<u32> = nodes.copied().collect();
+        nodes.sort();
+        nodes
+    }
+
+    #[test]
+    fn test_undirected_edges() {
+        let mut graph = AdjacencyTable::undirected();
+        assert!(graph.add_edge(1, 2));
+        assert!(graph.add_edge(1, 3));
+        assert!(graph.add_edge(3, 4));
+        assert!(!graph.add_edge(2, 1));
+
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.degree(&1), Some(2));
+        assert_eq!(graph.degree(&9), None);
+        assert!(graph.has_edge(&4, &3));
+
+        assert!(graph.remove_edge(&2, &1));
+        assert!(!graph.has_edge(&1, &2));
+        assert_eq!(sorted(graph.neighbors(&1)), vec![3]);
+
+        assert!(graph.remove_node(&3));
+        assert_eq!(graph.edge_count(), 0);
+        assert_eq!(graph.degree(&4), Some(0));
+        assert_eq!(graph.degree(&1), Some(0));
+    }
+
+    #[test]
+    fn test_directed_remove_node_drops_incoming_edges() {
+        let mut graph = AdjacencyTable::directed();
+        graph.add_edge("a", "b");
+        graph.add_edge("c", "b");
+        graph.add_edge("b", "d");
+        assert!(!graph.has_edge(&"b", &"a"));
+
+        assert!(graph.remove_node(&"b"));
+        assert_eq!(graph.edge_count(), 0);
+        assert_eq!(graph.degree(&"a"), Some(0));
+        assert_eq!(graph.degree(&"c"), Some(0));
+        assert!(!graph.contains_node(&"b"));
+    }
+
+    #[test]
+    fn test_traversals() {
+        let mut graph = AdjacencyTable::directed();
+        for (from, to) in [(1, 2), (1, 3), (2, 4), (3, 4), (4, 1), (5, 1)] {
+            graph.add_edge(from, to);
+        }
+
+        assert_eq!(graph.bfs(&1), vec![1, 2, 3, 4]);
+        assert_eq!(graph.dfs(&1), vec![1, 2, 4, 3]);
+        assert_eq!(graph.bfs(&5), vec![5, 1, 2, 3, 4]);
+        assert!(graph.dfs(&42).is_empty());
+    }
+}
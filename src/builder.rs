@@ -0,0 +1,220 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{slots_for, Error, HashTable, Probing, DEFAULT_LOAD_FACTOR};
+
+#[derive(Debug, Clone, Copy)]
+pub struct HashTableBuilder {
+    capacity: usize,
+    load_factor: f64,
+    probing: Probing,
+    salt: u64,
+}
+
+impl HashTableBuilder {
+    pub fn new() -> Self {
+        Self {
+            capacity: 0,
+            load_factor: DEFAULT_LOAD_FACTOR,
+            probing: Probing::Linear,
+            salt: 0,
+        }
+    }
+
+    /// Number of entries the table should hold before its first resize.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Fraction of slots that may be occupied before the table grows.
+    ///
+    /// Panics unless `0.0 < load_factor < 1.0`; open addressing needs at
+    /// least one empty slot to terminate probing.
+    pub fn load_factor(mut self, load_factor: f64) -> Self {
+        assert!(
+            load_factor > 0.0 && load_factor < 1.0,
+            "load factor must be between 0 and 1 (exclusive)"
+        );
+        self.load_factor = load_factor;
+        self
+    }
+
+    pub fn probing(mut self, probing: Probing) -> Self {
+        self.probing = probing;
+        self
+    }
+
+    /// Mixes `salt` into slot selection. Tables that share a hasher but not
+    /// a salt place the same keys differently, so one table's collision
+    /// hot spots are not repeated in the others. 0, the default, disables
+    /// salting.
+    pub fn salt(mut self, salt: u64) -> Self {
+        self.salt = salt;
+        self
+    }
+
+    pub fn build<K, V>(self) -> HashTable<K, V>
+    where
+        K: Eq + Hash,
+    {
+        self.build_with_hasher(RandomState::new())
+    }
+
+    /// Like [`build`](Self::build), with keys hashed by `hash_builder`.
+    pub fn build_with_hasher<K, V, S>(self, hash_builder: S) -> HashTable<K, V, S>
+    where
+        K: Eq + Hash,
+        S: BuildHasher,
+    {
+        let mut table = HashTable::with_slots(
+            slots_for(self.capacity, self.load_factor),
+            self.load_factor,
+            self.probing,
+            hash_builder,
+        );
+        table.salt = self.salt;
+        table
+    }
+
+    /// Like [`build`](Self::build), but reports allocation failure instead
+    /// of aborting.
+    pub fn try_build<K, V>(self) -> Result<HashTable<K, V>, Error>
+    where
+        K: Eq + Hash,
+    {
+        self.try_build_with_hasher(RandomState::new())
+    }
+
+    /// Like [`build_with_hasher`](Self::build_with_hasher), but reports
+    /// allocation failure instead of aborting.
+    pub fn try_build_with_hasher<K, V, S>(
+        self,
+        hash_builder: S,
+    ) -> Result<HashTable<K, V, S>, Error>
+    where
+        K: Eq + Hash,
+        S: BuildHasher,
+    {
+        let mut table = HashTable::try_with_slots(
+            slots_for(self.capacity, self.load_factor),
+            self.load_factor,
+            self.probing,
+            hash_builder,
+        )?;
+        table.salt = self.salt;
+        Ok(table)
+    }
+}
+
+impl Default for HashTableBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::INITIAL_CAPACITY;
+
+    #[test]
+    fn test_build_defaults() {
+        let table: HashTable<&str, i32> = HashTableBuilder::new().build();
+        assert_eq!(table.slots.len(), INITIAL_CAPACITY);
+        assert_eq!(table.load_factor, DEFAULT_LOAD_FACTOR);
+    }
+
+    #[test]
+    fn test_build_with_capacity_does_not_resize() {
+        let mut table: HashTable<i32, i32> = HashTableBuilder::new().capacity(100).build();
+        let slot_count = table.slots.len();
+
+        for i in 0..100 {
+            table.insert(i, i);
+        }
+
+        assert_eq!(table.slots.len(), slot_count);
+        for i in 0..100 {
+            assert_eq!(table.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_build_with_load_factor() {
+        let mut table: HashTable<i32, i32> = HashTableBuilder::new().load_factor(0.75).build();
+
+        for i in 0..12 {
+            table.insert(i, i);
+        }
+        assert_eq!(table.slots.len(), INITIAL_CAPACITY);
+
+        table.insert(12, 12);
+        assert_eq!(table.slots.len(), INITIAL_CAPACITY * 2);
+    }
+
+    #[test]
+    fn test_try_build_reports_capacity_overflow() {
+        let result = HashTableBuilder::new()
+            .capacity(usize::MAX)
+            .try_build::<u64, u64>();
+        assert_eq!(result.err(), Some(Error::CapacityOverflow));
+
+        let table = HashTableBuilder::new().capacity(8).try_build::<u64, u64>();
+        assert!(table.is_ok());
+    }
+
+    #[test]
+    fn test_try_build_with_hasher() {
+        let hasher = RandomState::new();
+        let result = HashTableBuilder::new()
+            .capacity(usize::MAX)
+            .try_build_with_hasher::<u64, u64, _>(hasher.clone());
+        assert_eq!(result.err(), Some(Error::CapacityOverflow));
+
+        let mut table: HashTable<u64, u64> = HashTableBuilder::new()
+            .capacity(8)
+            .salt(7)
+            .try_build_with_hasher(hasher.clone())
+            .unwrap();
+        let plain: HashTable<u64, u64> = HashTableBuilder::new().build_with_hasher(hasher);
+        assert_eq!(table.salt(), 7);
+        assert_eq!(table.make_hash(&1), plain.make_hash(&1));
+        table.insert(1, 1);
+        assert_eq!(table.get(&1), Some(&1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_build_rejects_full_load_factor() {
+        HashTableBuilder::new().load_factor(1.0);
+    }
+
+    #[test]
+    fn test_salt_decorrelates_shared_hasher() {
+        let hasher = RandomState::new();
+        let plain: HashTable<u32, u32> = HashTableBuilder::new().build_with_hasher(hasher.clone());
+        let salted: HashTable<u32, u32> = HashTableBuilder::new()
+            .salt(0x5eed)
+            .probing(Probing::TwoChoice)
+            .build_with_hasher(hasher);
+        assert_eq!(salted.salt(), 0x5eed);
+
+        let same_home = (0..1000)
+            .filter(|key| plain.hash(key) == salted.hash(key))
+            .count();
+        assert!(same_home < 200, "{same_home} of 1000 keys share a home");
+
+        let mut salted = salted;
+        for key in 0..1000 {
+            salted.insert(key, key);
+        }
+        salted.remove(&10);
+        assert_eq!(salted.get(&999), Some(&999));
+        assert_eq!(salted.get(&10), None);
+        salted.check_integrity().unwrap();
+        assert_eq!(salted.split_off_by(|key, _| key % 2 == 0).salt(), 0x5eed);
+    }
+}
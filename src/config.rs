@@ -0,0 +1,141 @@
+use std::{fmt, sync::Arc};
+
+use crate::{HashTable, SwappableTable};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The key is present but its value does not parse as the requested type.
+    TypeMismatch {
+        key: String,
+        expected: &'static str,
+        value: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::TypeMismatch {
+                key,
+                expected,
+                value,
+            } => write!(f, "config key {key:?} is {value:?}, expected {expected}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Typed lookups over a reloadable string-to-string table.
+///
+/// Missing keys fall back to the caller's default; present keys whose value
+/// does not parse are reported as [`ConfigError::TypeMismatch`] rather than
+/// silently defaulted.
+pub struct ConfigTable {
+    table: SwappableTable<String, String>,
+}
+
+impl ConfigTable {
+    pub fn new(values: HashTable<String, String>) -> Self {
+        Self {
+            table: SwappableTable::new(values),
+        }
+    }
+
+    /// Publishes a new set of values; readers switch over atomically.
+    pub fn reload(&self, values: HashTable<String, String>) {
+        self.table.store(values);
+    }
+
+    pub fn snapshot(&self) -> Arc<HashTable<String, String>> {
+        self.table.load()
+    }
+
+    pub fn get_str(&self, key: &str, default: &str) -> String {
+        self.snapshot()
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    /// Accepts `true`/`false`, `yes`/`no`, `on`/`off` and `1`/`0`, ignoring
+    /// case and surrounding whitespace.
+    pub fn get_bool(&self, key: &str, default: bool) -> Result<bool, ConfigError> {
+        self.get_parsed(key, default, "bool", |value| {
+            match value.trim().to_ascii_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Some(true),
+                "false" | "no" | "off" | "0" => Some(false),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn get_int(&self, key: &str, default: i64) -> Result<i64, ConfigError> {
+        self.get_parsed(key, default, "integer", |value| value.trim().parse().ok())
+    }
+
+    fn get_parsed<T>(
+        &self,
+        key: &str,
+        default: T,
+        expected: &'static str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Result<T, ConfigError> {
+        let snapshot = self.snapshot();
+        let Some(value) = snapshot.get(key) else {
+            return Ok(default);
+        };
+
+        parse(value).ok_or_else(|| ConfigError::TypeMismatch {
+            key: key.to_string(),
+            expected,
+            value: value.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashTable<String, String> {
+        let mut table = HashTable::new();
+        for (key, value) in pairs {
+            table.insert(key.to_string(), value.to_string());
+        }
+        table
+    }
+
+    #[test]
+    fn test_typed_getters() {
+        let config = ConfigTable::new(values(&[
+            ("enabled", "Yes"),
+            ("retries", " 3 "),
+            ("name", "svc"),
+        ]));
+
+        assert_eq!(config.get_bool("enabled", false), Ok(true));
+        assert_eq!(config.get_bool("missing", true), Ok(true));
+        assert_eq!(config.get_int("retries", 0), Ok(3));
+        assert_eq!(config.get_str("name", "default"), "svc");
+        assert_eq!(config.get_str("missing", "default"), "default");
+
+        assert_eq!(
+            config.get_int("name", 0),
+            Err(ConfigError::TypeMismatch {
+                key: "name".to_string(),
+                expected: "integer",
+                value: "svc".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_reload() {
+        let config = ConfigTable::new(values(&[("enabled", "off")]));
+        assert_eq!(config.get_bool("enabled", true), Ok(false));
+
+        config.reload(values(&[("enabled", "on")]));
+        assert_eq!(config.get_bool("enabled", false), Ok(true));
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{BuildHasher, BuildHasherDefault},
+};
+
+use crate::HashTable;
+
+/// Identifies a blob in a [`ContentStore`]. Derived from the blob's hash, so
+/// equal content always gets the same id within a store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ContentId(u64);
+
+impl ContentId {
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for ContentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+#[derive(Clone)]
+struct Blob {
+    bytes: Vec<u8>,
+    refs: usize,
+    /// The content hash the blob's id was probed from.
+    base: u64,
+}
+
+/// The ids probed from one content hash.
+#[derive(Clone, Default)]
+struct Chain {
+    /// Ids `base..base + span` may hold blobs with this hash.
+    span: u64,
+    /// Blobs with this hash currently stored.
+    live: usize,
+}
+
+/// Deduplicating, reference-counted blob storage keyed by content hash.
+///
+/// Storing the same bytes twice keeps one copy and bumps its reference
+/// count; the blob is dropped once every reference is released. Hash
+/// collisions between different blobs are resolved by moving to the next
+/// free id, so ids are only stable for the lifetime of the store.
+pub struct ContentStore<S = BuildHasherDefault<DefaultHasher>> {
+    blobs: HashTable<ContentId, Blob>,
+    /// Keyed by content hash. Releasing a blob can free an id in the middle
+    /// of a chain, so lookups scan the whole span rather than stopping at
+    /// the first free id.
+    chains: HashTable<u64, Chain>,
+    hasher: S,
+}
+
+impl ContentStore {
+    pub fn new() -> Self {
+        Self::with_hasher(BuildHasherDefault::default())
+    }
+}
+
+impl<S: BuildHasher> ContentStore<S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            blobs: HashTable::new(),
+            chains: HashTable::new(),
+            hasher,
+        }
+    }
+
+    /// Number of distinct blobs.
+    pub fn len(&self) -> usize {
+        self.blobs.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stores `bytes`, or takes another reference to an identical blob
+    /// already in the store.
+    pub fn put(&mut self, bytes: &[u8]) -> ContentId {
+        let base = self.hasher.hash_one(bytes);
+        let span = self.chains.get(&base).map_or(0, |chain| chain.span);
+        for offset in 0..span {
+            let id = ContentId(base.wrapping_add(offset));
+            if let Some(blob) = self.blobs.get_mut(&id) {
+                if blob.bytes == bytes {
+                    blob.refs += 1;
+                    return id;
+                }
+            }
+        }
+
+        let mut offset = 0;
+        while self
+            .blobs
+            .contains_key(&ContentId(base.wrapping_add(offset)))
+        {
+            offset += 1;
+        }
+        let id = ContentId(base.wrapping_add(offset));
+        let blob = Blob {
+            bytes: bytes.to_vec(),
+            refs: 1,
+            base,
+        };
+        self.blobs.insert(id, blob);
+        let chain = self.chains.entry(base).or_default();
+        chain.span = chain.span.max(offset + 1);
+        chain.live += 1;
+        id
+    }
+
+    pub fn get(&self, id: ContentId) -> Option<&[u8]> {
+        self.blobs.get(&id).map(|blob| blob.bytes.as_slice())
+    }
+
+    pub fn contains(&self, id: ContentId) -> bool {
+        self.blobs.get(&id).is_some()
+    }
+
+    pub fn ref_count(&self, id: ContentId) -> usize {
+        self.blobs.get(&id).map_or(0, |blob| blob.refs)
+    }
+
+    /// Takes another reference to a stored blob. Returns `false` if `id` is
+    /// not in the store.
+    pub fn retain(&mut self, id: ContentId) -> bool {
+        let Some(index) = self.blobs.find_slot(&id) else {
+            return false;
+        };
+        self.blobs.slots[index].as_mut().unwrap().value.refs += 1;
+        true
+    }
+
+    /// Drops one reference, removing the blob when none are left. Returns
+    /// the blob's bytes if this released the last reference.
+    pub fn release(&mut self, id: ContentId) -> Option<Vec<u8>> {
+        let index = self.blobs.find_slot(&id)?;
+        let blob = &mut self.blobs.slots[index].as_mut().unwrap().value;
+        blob.refs -= 1;
+        if blob.refs > 0 {
+            return None;
+        }
+
+        let blob = self.blobs.remove(&id)?;
+        let chain = self.chains.get_mut(&blob.base).unwrap();
+        chain.live -= 1;
+        if chain.live == 0 {
+            self.chains.remove(&blob.base);
+        }
+        Some(blob.bytes)
+    }
+}
+
+impl Default for ContentStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::hash::Hasher;
+
+    #[test]
+    fn test_deduplicates_and_counts_references() {
+        let mut store = ContentStore::new();
+        let logo = store.put(b"logo.png bytes");
+        let css = store.put(b"body {}");
+        assert_eq!(store.put(b"logo.png bytes"), logo);
+        assert_ne!(logo, css);
+
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.ref_count(logo), 2);
+        assert_eq!(store.get(css), Some(&b"body {}"[..]));
+
+        assert_eq!(store.release(logo), None);
+        assert!(store.contains(logo));
+        assert_eq!(store.release(logo), Some(b"logo.png bytes".to_vec()));
+        assert!(!store.contains(logo));
+        assert_eq!(store.get(logo), None);
+
+        assert!(store.retain(css));
+        assert!(!store.retain(logo));
+        assert_eq!(store.ref_count(css), 2);
+    }
+
+    #[derive(Default)]
+    struct LengthHasher(u64);
+
+    impl Hasher for LengthHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0 += bytes.len() as u64;
+        }
+    }
+
+    #[test]
+    fn test_hash_collisions_get_distinct_ids() {
+        let mut store = ContentStore::with_hasher(BuildHasherDefault::<LengthHasher>::default());
+        let a = store.put(b"aaaa");
+        let b = store.put(b"bbbb");
+
+        assert_ne!(a, b);
+        assert_eq!(store.get(a), Some(&b"aaaa"[..]));
+        assert_eq!(store.get(b), Some(&b"bbbb"[..]));
+        assert_eq!(store.put(b"bbbb"), b);
+    }
+
+    #[test]
+    fn test_dedup_survives_releasing_chain_head() {
+        let mut store = ContentStore::with_hasher(BuildHasherDefault::<LengthHasher>::default());
+        let a = store.put(b"aaaa");
+        let b = store.put(b"bbbb");
+        assert_eq!(store.release(a), Some(b"aaaa".to_vec()));
+
+        assert_eq!(store.put(b"bbbb"), b);
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.ref_count(b), 2);
+
+        let c = store.put(b"cccc");
+        assert_eq!(c, a);
+        assert_eq!(store.put(b"cccc"), c);
+        assert_eq!(store.len(), 2);
+
+        for id in [b, b, c, c] {
+            store.release(id);
+        }
+        assert!(store.is_empty());
+        assert!(store.chains.is_empty());
+    }
+}
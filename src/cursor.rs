@@ -0,0 +1,172 @@
+//! Resumable, page-at-a-time iteration that tolerates changes to the table
+//! between pages.
+
+use std::hash::{BuildHasher, Hash};
+
+use crate::HashTable;
+
+/// Where the next [`HashTable::iter_from`] page starts. Convert it to a
+/// `u64` with [`to_token`](Self::to_token) to hand it to a client.
+///
+/// Pages are ordered by the keys' cached hashes, so a cursor only makes
+/// sense for the table that produced it (or a clone of it).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Cursor(u64);
+
+impl Cursor {
+    pub const START: Cursor = Cursor(0);
+
+    pub fn to_token(self) -> u64 {
+        self.0
+    }
+
+    pub fn from_token(token: u64) -> Self {
+        Cursor(token)
+    }
+}
+
+/// One page of entries from [`HashTable::iter_from`].
+pub struct Page<'a, K, V> {
+    pub entries: Vec<(&'a K, &'a V)>,
+    /// Where the following page starts; `None` once the table is exhausted.
+    pub next: Option<Cursor>,
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Returns up to `limit` entries starting at `cursor`, plus the cursor
+    /// for the next page. The table can be modified freely between calls.
+    ///
+    /// Entries come out in order of their hash, which neither resizing nor
+    /// removal changes. Across a full pagination every entry present from
+    /// start to finish is yielded exactly once, and no entry is ever
+    /// yielded twice; entries inserted or removed along the way may or may
+    /// not be seen. A page can exceed `limit` when several keys share a
+    /// full 64-bit hash, since those cannot be split across pages.
+    ///
+    /// Each call scans the whole table and sorts only the page it returns,
+    /// so a page costs O(n + limit log limit) for a table of `n` entries.
+    ///
+    /// Panics if `limit` is zero.
+    pub fn iter_from(&self, cursor: Cursor, limit: usize) -> Page<'_, K, V> {
+        assert!(limit > 0, "page limit must be at least 1");
+
+        let mut page: Vec<_> = self
+            .slots
+            .iter()
+            .flatten()
+            .filter(|bucket| bucket.hash >= cursor.0)
+            .collect();
+
+        let mut next = None;
+        if page.len() > limit {
+            // Move the `limit` lowest hashes to the front, then pull in any
+            // other entries sharing the highest of them.
+            page.select_nth_unstable_by_key(limit - 1, |bucket| bucket.hash);
+            let last = page[limit - 1].hash;
+            let mut end = limit;
+            for at in limit..page.len() {
+                if page[at].hash == last {
+                    page.swap(at, end);
+                    end += 1;
+                }
+            }
+            if end < page.len() {
+                next = Some(Cursor(last + 1));
+            }
+            page.truncate(end);
+        }
+        page.sort_unstable_by_key(|bucket| bucket.hash);
+
+        let entries = page
+            .into_iter()
+            .map(|bucket| (&bucket.key, &bucket.value))
+            .collect();
+        Page { entries, next }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_pages_cover_table() {
+        let table: HashTable<u32, u32> = (0..1000).map(|n| (n, n)).collect();
+
+        let mut seen = HashSet::new();
+        let mut cursor = Some(Cursor::START);
+        while let Some(current) = cursor {
+            let page = table.iter_from(Cursor::from_token(current.to_token()), 64);
+            assert!(page.entries.len() <= 64);
+            let hashes: Vec<u64> = page
+                .entries
+                .iter()
+                .map(|(key, _)| table.make_hash(*key))
+                .collect();
+            assert!(hashes.windows(2).all(|pair| pair[0] < pair[1]));
+            for (key, _) in page.entries {
+                assert!(seen.insert(*key));
+            }
+            cursor = page.next;
+        }
+        assert_eq!(seen.len(), 1000);
+    }
+
+    #[test]
+    fn test_pages_survive_concurrent_changes() {
+        let mut table: HashTable<u32, u32> = (0..300).map(|n| (n, n)).collect();
+
+        let mut seen = HashSet::new();
+        let mut cursor = Some(Cursor::START);
+        let mut round = 0;
+        while let Some(current) = cursor {
+            let page = table.iter_from(current, 20);
+            for (key, _) in &page.entries {
+                assert!(seen.insert(**key), "key {key} yielded twice");
+            }
+            cursor = page.next;
+
+            // Grow the table, shift clusters with removals and re-add.
+            round += 1;
+            for n in 0..30 {
+                table.insert(10_000 + round * 30 + n, 0);
+            }
+            table.remove(&(round * 7 % 150 + 150));
+            table.insert(round * 7 % 150 + 150, 0);
+        }
+
+        // Keys 0..150 were never touched, so each was seen exactly once.
+        assert!((0..150).all(|key| seen.contains(&key)));
+    }
+
+    #[test]
+    fn test_shared_hash_stays_on_one_page() {
+        use std::hash::{BuildHasherDefault, Hasher};
+
+        #[derive(Default)]
+        struct Constant;
+
+        impl Hasher for Constant {
+            fn finish(&self) -> u64 {
+                u64::MAX
+            }
+
+            fn write(&mut self, _bytes: &[u8]) {}
+        }
+
+        let mut table = HashTable::with_hasher(BuildHasherDefault::<Constant>::default());
+        for n in 0..5 {
+            table.insert(n, n);
+        }
+
+        let page = table.iter_from(Cursor::START, 2);
+        assert_eq!(page.entries.len(), 5);
+        assert_eq!(page.next, None);
+    }
+}
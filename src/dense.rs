@@ -0,0 +1,163 @@
+//! Integer-keyed map that stores small keys in a directly indexed array.
+
+use crate::HashTable;
+
+const DEFAULT_DENSE_THRESHOLD: usize = 1024;
+
+/// A map from `usize` keys that keeps keys below `threshold` in a dense
+/// array indexed by the key itself and sends larger keys to a
+/// [`HashTable`]. Lookups of small keys never hash.
+///
+/// The methods mirror [`HashTable`]'s, except that [`iter`](Self::iter)
+/// yields keys by value since small keys are not stored.
+pub struct DenseMap<V> {
+    dense: Vec<Option<V>>,
+    dense_len: usize,
+    sparse: HashTable<usize, V>,
+    threshold: usize,
+}
+
+impl<V> DenseMap<V> {
+    pub fn new() -> Self {
+        Self::with_threshold(DEFAULT_DENSE_THRESHOLD)
+    }
+
+    /// Keys below `threshold` are stored densely. The dense array only
+    /// grows as far as the largest small key inserted.
+    pub fn with_threshold(threshold: usize) -> Self {
+        Self {
+            dense: Vec::new(),
+            dense_len: 0,
+            sparse: HashTable::new(),
+            threshold,
+        }
+    }
+
+    /// Inserts `value` under `key` and returns the value it displaced, if
+    /// any.
+    pub fn insert(&mut self, key: usize, value: V) -> Option<V> {
+        if key >= self.threshold {
+            return self.sparse.insert(key, value);
+        }
+
+        if key >= self.dense.len() {
+            self.dense.resize_with(key + 1, || None);
+        }
+        let old = self.dense[key].replace(value);
+        if old.is_none() {
+            self.dense_len += 1;
+        }
+        old
+    }
+
+    pub fn get(&self, key: &usize) -> Option<&V> {
+        if *key >= self.threshold {
+            return self.sparse.get(key);
+        }
+        self.dense.get(*key)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, key: &usize) -> Option<&mut V> {
+        if *key >= self.threshold {
+            return self.sparse.get_mut(key);
+        }
+        self.dense.get_mut(*key)?.as_mut()
+    }
+
+    pub fn contains_key(&self, key: &usize) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn remove(&mut self, key: &usize) -> Option<V> {
+        if *key >= self.threshold {
+            return self.sparse.remove(key);
+        }
+
+        let value = self.dense.get_mut(*key)?.take()?;
+        self.dense_len -= 1;
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense_len + self.sparse.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Small keys in ascending order, then large keys in no particular
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &V)> {
+        let dense = self
+            .dense
+            .iter()
+            .enumerate()
+            .filter_map(|(key, value)| Some((key, value.as_ref()?)));
+        dense.chain(self.sparse.iter().map(|(&key, value)| (key, value)))
+    }
+}
+
+impl<V> Default for DenseMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dense_and_sparse_keys() {
+        let mut map: DenseMap<&str> = DenseMap::with_threshold(100);
+        map.insert(3, "three");
+        map.insert(99, "ninety-nine");
+        map.insert(100, "hundred");
+        map.insert(1_000_000, "million");
+
+        assert_eq!(map.dense.len(), 100);
+        assert_eq!(map.sparse.size, 2);
+        assert_eq!(map.len(), 4);
+
+        assert_eq!(map.get(&3), Some(&"three"));
+        assert_eq!(map.get(&4), None);
+        assert_eq!(map.get(&500), None);
+        assert_eq!(map.get(&1_000_000), Some(&"million"));
+
+        assert_eq!(map.insert(3, "THREE"), Some("three"));
+        assert_eq!(map.insert(1_000_000, "MILLION"), Some("million"));
+        assert_eq!(map.insert(4, "four"), None);
+        assert_eq!(map.get(&3), Some(&"THREE"));
+        assert_eq!(map.len(), 5);
+    }
+
+    #[test]
+    fn test_get_mut_contains_key_and_iter() {
+        let mut map: DenseMap<i32> = DenseMap::with_threshold(10);
+        for key in [7, 2, 40, 5] {
+            map.insert(key, 0);
+        }
+        *map.get_mut(&2).unwrap() += 1;
+        *map.get_mut(&40).unwrap() += 2;
+        assert_eq!(map.get_mut(&3), None);
+        assert!(map.contains_key(&40));
+        assert!(!map.contains_key(&41));
+
+        let entries: Vec<_> = map.iter().collect();
+        assert_eq!(entries, [(2, &1), (5, &0), (7, &0), (40, &2)]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map: DenseMap<i32> = DenseMap::with_threshold(10);
+        map.insert(1, 1);
+        map.insert(50, 50);
+
+        assert_eq!(map.remove(&1), Some(1));
+        assert_eq!(map.remove(&1), None);
+        assert_eq!(map.remove(&50), Some(50));
+        assert_eq!(map.remove(&9), None);
+        assert!(map.is_empty());
+    }
+}
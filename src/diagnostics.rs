@@ -0,0 +1,249 @@
+use std::{
+    fmt::{Debug, Write},
+    hash::{BuildHasher, Hash},
+};
+
+use crate::HashTable;
+
+/// A home bucket shared by more than one stored key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision<'a, K> {
+    pub home: usize,
+    pub keys: Vec<&'a K>,
+}
+
+/// Read-only view of one slot, as yielded by [`HashTable::buckets`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BucketInfo {
+    pub index: usize,
+    pub occupied: bool,
+    /// Distance from the entry's home bucket; `None` for empty slots.
+    pub probe_distance: Option<usize>,
+    /// The entry's cached full hash; `None` for empty slots.
+    pub hash: Option<u64>,
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Iterates over every slot in index order, occupied or not.
+    pub fn buckets(&self) -> impl Iterator<Item = BucketInfo> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .map(|(index, slot)| match slot {
+                Some(bucket) => BucketInfo {
+                    index,
+                    occupied: true,
+                    probe_distance: Some(self.probe_distance(index, bucket.hash)),
+                    hash: Some(bucket.hash),
+                },
+                None => BucketInfo {
+                    index,
+                    occupied: false,
+                    probe_distance: None,
+                    hash: None,
+                },
+            })
+    }
+
+    /// Iterates over the entries in slot order as `(key, value, home,
+    /// probe_distance)`, where `home` is the slot the entry's probe chain
+    /// starts from.
+    pub fn iter_with_metadata(&self) -> impl Iterator<Item = (&K, &V, usize, usize)> + '_ {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let bucket = slot.as_ref()?;
+            let home = self.home_for_slot(index, bucket.hash);
+            let distance = self.probe_distance(index, bucket.hash);
+            Some((&bucket.key, &bucket.value, home, distance))
+        })
+    }
+
+    pub(crate) fn probe_distance(&self, index: usize, hash: u64) -> usize {
+        let capacity = self.slots.len();
+        (index + capacity - self.home_for_slot(index, hash)) % capacity
+    }
+
+    /// The home bucket whose probe chain leads to `index`. With two-choice
+    /// probing that is whichever of the two homes is closer.
+    fn home_for_slot(&self, index: usize, hash: u64) -> usize {
+        let capacity = self.slots.len();
+        let distance = |home: usize| (index + capacity - home) % capacity;
+        let first = self.index_for(hash);
+
+        match self.second_home(hash) {
+            Some(second) if distance(second) < distance(first) => second,
+            _ => first,
+        }
+    }
+
+    /// Returns up to `top_n` home buckets with the most keys hashed to them,
+    /// most crowded first. Buckets with a single key are not collisions and
+    /// are left out.
+    pub fn collision_report(&self, top_n: usize) -> Vec<Collision<'_, K>> {
+        let mut by_home: Vec<Vec<&K>> = vec![Vec::new(); self.slots.len()];
+        for bucket in self.slots.iter().flatten() {
+            by_home[self.index_for(bucket.hash)].push(&bucket.key);
+        }
+
+        let mut collisions: Vec<Collision<'_, K>> = by_home
+            .into_iter()
+            .enumerate()
+            .filter(|(_, keys)| keys.len() > 1)
+            .map(|(home, keys)| Collision { home, keys })
+            .collect();
+
+        collisions.sort_by(|a, b| b.keys.len().cmp(&a.keys.len()).then(a.home.cmp(&b.home)));
+        collisions.truncate(top_n);
+        collisions
+    }
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash + Debug,
+    S: BuildHasher,
+{
+    /// Renders the slot array as a Graphviz graph. Occupied slots are
+    /// labelled with their key, and every key stored away from its home
+    /// bucket gets a dashed edge from the home slot, labelled with the
+    /// probe distance.
+    pub fn to_dot(&self) -> String {
+        let capacity = self.slots.len();
+        let mut dot = String::from("digraph HashTable {\n    node [shape=box];\n");
+
+        for (index, slot) in self.slots.iter().enumerate() {
+            match slot {
+                Some(bucket) => {
+                    let label = escape_label(&format!("{:?}", bucket.key));
+                    writeln!(dot, "    slot{index} [label=\"{index}\\n{label}\"];").unwrap();
+                }
+                None => {
+                    writeln!(dot, "    slot{index} [label=\"{index}\", style=dashed];").unwrap();
+                }
+            }
+        }
+
+        if capacity > 1 {
+            let order: Vec<String> = (0..capacity).map(|index| format!("slot{index}")).collect();
+            writeln!(dot, "    {} [style=invis];", order.join(" -> ")).unwrap();
+        }
+
+        for (index, slot) in self.slots.iter().enumerate() {
+            if let Some(bucket) = slot {
+                let home = self.home_for_slot(index, bucket.hash);
+                if home != index {
+                    let distance = self.probe_distance(index, bucket.hash);
+                    writeln!(
+                        dot,
+                        "    slot{home} -> slot{index} [style=dashed, label=\"{distance}\"];"
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn escape_label(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collision_report() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        for i in 0..200 {
+            table.insert(i, i);
+        }
+
+        let report = table.collision_report(3);
+        assert!(report.len() <= 3);
+        assert!(report
+            .windows(2)
+            .all(|pair| pair[0].keys.len() >= pair[1].keys.len()));
+        for collision in &report {
+            assert!(collision.keys.len() > 1);
+            for key in &collision.keys {
+                assert_eq!(table.hash(key), collision.home);
+            }
+        }
+
+        let all = table.collision_report(usize::MAX);
+        let colliding: usize = all.iter().map(|c| c.keys.len()).sum();
+        let homes = table
+            .slots
+            .iter()
+            .flatten()
+            .map(|bucket| table.hash(&bucket.key))
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(colliding - all.len(), table.size - homes.len());
+    }
+
+    #[test]
+    fn test_buckets() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        for i in 0..10 {
+            table.insert(i, i);
+        }
+
+        let buckets: Vec<BucketInfo> = table.buckets().collect();
+        assert_eq!(buckets.len(), table.slots.len());
+        assert_eq!(buckets.iter().filter(|b| b.occupied).count(), 10);
+
+        for info in &buckets {
+            match &table.slots[info.index] {
+                Some(bucket) => {
+                    assert_eq!(info.hash, Some(bucket.hash));
+                    let home = table.hash(&bucket.key);
+                    let distance = info.probe_distance.unwrap();
+                    assert_eq!((home + distance) % table.slots.len(), info.index);
+                }
+                None => assert_eq!(info.probe_distance, None),
+            }
+        }
+    }
+
+    #[test]
+    fn test_iter_with_metadata() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        for i in 0..10 {
+            table.insert(i, i * 2);
+        }
+
+        let entries: Vec<_> = table.iter_with_metadata().collect();
+        assert_eq!(entries.len(), 10);
+        for (key, value, home, distance) in entries {
+            assert_eq!(*value, key * 2);
+            assert_eq!(home, table.hash(key));
+            let index = (home + distance) % table.slots.len();
+            assert_eq!(table.slots[index].as_ref().map(|b| b.key), Some(*key));
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut table: HashTable<&str, i32> = HashTable::new();
+        table.insert("one", 1);
+        table.insert("say \"hi\"", 2);
+
+        let dot = table.to_dot();
+        assert!(dot.starts_with("digraph HashTable {"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches("[label=").count(), table.slots.len());
+        assert!(dot.contains(&format!(
+            "slot{} [label=\"{}\\n\\\"one\\\"\"];",
+            table.hash(&"one"),
+            table.hash(&"one")
+        )));
+        assert!(dot.contains("say \\\\\\\"hi\\\\\\\""));
+    }
+}
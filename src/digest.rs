@@ -0,0 +1,250 @@
+//! Digests of a table's contents for comparing tables across processes.
+
+use std::hash::{BuildHasher, Hash};
+
+use crate::HashTable;
+
+/// Order-independent running digest of a set of pair hashes.
+#[derive(Clone, Copy, Default)]
+struct Accumulator {
+    count: usize,
+    sum: u64,
+    xor: u64,
+}
+
+impl Accumulator {
+    fn add(&mut self, hash: u64) {
+        self.count += 1;
+        self.sum = self.sum.wrapping_add(hash);
+        self.xor ^= hash;
+    }
+
+    fn finish(self, hasher: &impl BuildHasher) -> u64 {
+        hasher.hash_one((self.count, self.sum, self.xor))
+    }
+}
+
+/// A Merkle tree over a table's contents, built by
+/// [`HashTable::merkle_digest`].
+///
+/// Entries are split into `2^depth` partitions by the top bits of their
+/// key's hash. Each leaf is the [`content_hash`](HashTable::content_hash)
+/// of one partition and each inner node hashes its two children, so two
+/// replicas can compare roots and walk down only the subtrees that differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleDigest {
+    depth: u32,
+    /// Heap order: the root is at 1 and node `i` has children `2i` and
+    /// `2i + 1`. Index 0 is unused.
+    nodes: Vec<u64>,
+}
+
+impl MerkleDigest {
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn root(&self) -> u64 {
+        self.nodes[1]
+    }
+
+    pub fn partition_count(&self) -> usize {
+        1 << self.depth
+    }
+
+    pub fn leaf(&self, partition: usize) -> u64 {
+        self.nodes[self.partition_count() + partition]
+    }
+
+    /// The partitions whose contents differ between the two digests, in
+    /// ascending order. Only subtrees with differing hashes are visited.
+    ///
+    /// Panics if the digests have different depths.
+    pub fn diff(&self, other: &MerkleDigest) -> Vec<usize> {
+        assert_eq!(self.depth, other.depth, "digests have different depths");
+
+        let mut differing = Vec::new();
+        let mut pending = vec![1];
+        while let Some(node) = pending.pop() {
+            if self.nodes[node] == other.nodes[node] {
+                continue;
+            }
+            if node >= self.partition_count() {
+                differing.push(node - self.partition_count());
+            } else {
+                pending.extend([2 * node + 1, 2 * node]);
+            }
+        }
+        differing
+    }
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// A digest of every `(key, value)` pair that does not depend on slot
+    /// order, so equal contents give equal digests whatever the insertion
+    /// history, capacity or probing mode.
+    ///
+    /// Each pair is hashed with `hasher` and the results are combined with
+    /// commutative operations. To compare digests between processes,
+    /// `hasher` must be deterministic (for example
+    /// `BuildHasherDefault<DefaultHasher>`), not a `RandomState`.
+    pub fn content_hash<H: BuildHasher>(&self, hasher: &H) -> u64
+    where
+        V: Hash,
+    {
+        let mut digest = Accumulator::default();
+        for pair in self.iter() {
+            digest.add(hasher.hash_one(pair));
+        }
+        digest.finish(hasher)
+    }
+
+    /// Builds a [`MerkleDigest`] with `2^depth` partitions. As with
+    /// [`content_hash`](Self::content_hash), `hasher` must be deterministic
+    /// for digests to be comparable across processes.
+    ///
+    /// Panics if `depth` exceeds 24.
+    pub fn merkle_digest<H: BuildHasher>(&self, hasher: &H, depth: u32) -> MerkleDigest
+    where
+        V: Hash,
+    {
+        assert!(depth <= 24, "merkle depth {depth} is too large");
+
+        let partitions = 1usize << depth;
+        let mut leaves = vec![Accumulator::default(); partitions];
+        for (key, value) in self.iter() {
+            leaves[partition_of(hasher, key, depth)].add(hasher.hash_one((key, value)));
+        }
+
+        let mut nodes = vec![0; 2 * partitions];
+        for (partition, leaf) in leaves.into_iter().enumerate() {
+            nodes[partitions + partition] = leaf.finish(hasher);
+        }
+        for node in (1..partitions).rev() {
+            nodes[node] = hasher.hash_one((nodes[2 * node], nodes[2 * node + 1]));
+        }
+
+        MerkleDigest { depth, nodes }
+    }
+
+    /// The entries that [`merkle_digest`](Self::merkle_digest) with the same
+    /// `hasher` and `depth` puts in `partition`: what a replica sends for a
+    /// partition reported by [`MerkleDigest::diff`].
+    pub fn partition_entries<'a, H: BuildHasher>(
+        &'a self,
+        hasher: &'a H,
+        depth: u32,
+        partition: usize,
+    ) -> impl Iterator<Item = (&'a K, &'a V)> + 'a {
+        self.iter()
+            .filter(move |(key, _)| partition_of(hasher, *key, depth) == partition)
+    }
+}
+
+fn partition_of<K: Hash + ?Sized>(hasher: &impl BuildHasher, key: &K, depth: u32) -> usize {
+    hasher.hash_one(key).checked_shr(64 - depth).unwrap_or(0) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::hash_map::DefaultHasher, hash::BuildHasherDefault};
+
+    use crate::{HashTable, HashTableBuilder, Probing};
+
+    #[test]
+    fn test_content_hash_ignores_layout() {
+        let digest = BuildHasherDefault::<DefaultHasher>::default();
+
+        let mut forward: HashTable<u32, String> = HashTable::new();
+        for n in 0..200 {
+            forward.insert(n, n.to_string());
+        }
+        let mut backward = HashTableBuilder::new()
+            .capacity(1000)
+            .probing(Probing::TwoChoice)
+            .build();
+        for n in (0..250).rev() {
+            backward.insert(n, n.to_string());
+        }
+        for n in 200..250 {
+            backward.remove(&n);
+        }
+
+        assert_eq!(
+            forward.content_hash(&digest),
+            backward.content_hash(&digest)
+        );
+
+        backward.insert(7, "seven".to_string());
+        assert_ne!(
+            forward.content_hash(&digest),
+            backward.content_hash(&digest)
+        );
+        assert_ne!(
+            HashTable::<u32, u32>::new().content_hash(&digest),
+            forward.content_hash(&digest)
+        );
+    }
+
+    #[test]
+    fn test_merkle_diff_finds_changed_partitions() {
+        let digest = BuildHasherDefault::<DefaultHasher>::default();
+        let mut primary: HashTable<u32, u32> = (0..5000).map(|n| (n, n)).collect();
+        let mut replica: HashTable<u32, u32> = (0..5000).rev().map(|n| (n, n)).collect();
+
+        let tree = primary.merkle_digest(&digest, 6);
+        assert_eq!(tree.partition_count(), 64);
+        assert_eq!(tree, replica.merkle_digest(&digest, 6));
+        assert_eq!(tree.root(), replica.merkle_digest(&digest, 6).root());
+
+        replica.insert(17, 0);
+        replica.remove(&4000);
+        primary.insert(9999, 1);
+        let primary_tree = primary.merkle_digest(&digest, 6);
+        let replica_tree = replica.merkle_digest(&digest, 6);
+
+        let changed = primary_tree.diff(&replica_tree);
+        let mut expected: Vec<usize> = [17, 4000, 9999]
+            .iter()
+            .map(|key| super::partition_of(&digest, key, 6))
+            .collect();
+        expected.sort();
+        expected.dedup();
+        assert_eq!(changed, expected);
+
+        for partition in changed {
+            let entries: Vec<(u32, u32)> = primary
+                .partition_entries(&digest, 6, partition)
+                .map(|(key, value)| (*key, *value))
+                .collect();
+            let stale: Vec<u32> = replica
+                .partition_entries(&digest, 6, partition)
+                .map(|(key, _)| *key)
+                .collect();
+            for key in stale {
+                replica.remove(&key);
+            }
+            replica.extend(entries);
+        }
+        assert_eq!(
+            primary.merkle_digest(&digest, 6),
+            replica.merkle_digest(&digest, 6)
+        );
+        assert_eq!(primary.content_hash(&digest), replica.content_hash(&digest));
+    }
+
+    #[test]
+    fn test_merkle_depth_zero_is_content_hash_tree() {
+        let digest = BuildHasherDefault::<DefaultHasher>::default();
+        let table: HashTable<u32, u32> = (0..10).map(|n| (n, n)).collect();
+
+        let tree = table.merkle_digest(&digest, 0);
+        assert_eq!(tree.partition_count(), 1);
+        assert_eq!(tree.root(), tree.leaf(0));
+        assert_eq!(tree.root(), table.content_hash(&digest));
+    }
+}
@@ -0,0 +1,185 @@
+use std::hash::Hash;
+
+use crate::HashTable;
+
+/// Union-find over hashable elements, with path compression and union by
+/// rank.
+///
+/// Elements are mapped to dense indices through a [`HashTable`]; the forest
+/// itself lives in plain vectors indexed by those.
+pub struct DisjointSet<K: Eq + Hash + Clone> {
+    indices: HashTable<K, usize>,
+    elements: Vec<K>,
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    groups: usize,
+}
+
+impl<K> DisjointSet<K>
+where
+    K: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            indices: HashTable::new(),
+            elements: Vec::new(),
+            parent: Vec::new(),
+            rank: Vec::new(),
+            groups: 0,
+        }
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Number of disjoint groups.
+    pub fn group_count(&self) -> usize {
+        self.groups
+    }
+
+    /// Adds `key` as a singleton group. Returns `false` if it was already
+    /// present.
+    pub fn insert(&mut self, key: K) -> bool {
+        if self.indices.get(&key).is_some() {
+            return false;
+        }
+        self.index_of(key);
+        true
+    }
+
+    /// The representative of `key`'s group, or `None` if `key` was never
+    /// added.
+    pub fn find(&mut self, key: &K) -> Option<&K> {
+        let index = *self.indices.get(key)?;
+        let root = self.root(index);
+        Some(&self.elements[root])
+    }
+
+    /// Merges the groups of `a` and `b`, adding either if missing. Returns
+    /// `false` if they were already in the same group.
+    pub fn union(&mut self, a: K, b: K) -> bool {
+        let a = self.index_of(a);
+        let b = self.index_of(b);
+        let (a, b) = (self.root(a), self.root(b));
+        if a == b {
+            return false;
+        }
+
+        let (child, parent) = if self.rank[a] < self.rank[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[child] = parent;
+        if self.rank[child] == self.rank[parent] {
+            self.rank[parent] += 1;
+        }
+        self.groups -= 1;
+        true
+    }
+
+    pub fn same_group(&mut self, a: &K, b: &K) -> bool {
+        match (self.indices.get(a).copied(), self.indices.get(b).copied()) {
+            (Some(a), Some(b)) => self.root(a) == self.root(b),
+            _ => false,
+        }
+    }
+
+    /// Every group's members, in insertion order within each group.
+    pub fn groups(&self) -> impl Iterator<Item = Vec<&K>> {
+        let mut groups: Vec<Vec<&K>> = vec![Vec::new(); self.len()];
+        for (index, element) in self.elements.iter().enumerate() {
+            let mut root = index;
+            while self.parent[root] != root {
+                root = self.parent[root];
+            }
+            groups[root].push(element);
+        }
+        groups.into_iter().filter(|group| !group.is_empty())
+    }
+
+    fn index_of(&mut self, key: K) -> usize {
+        if let Some(&index) = self.indices.get(&key) {
+            return index;
+        }
+
+        let index = self.elements.len();
+        self.indices.insert(key.clone(), index);
+        self.elements.push(key);
+        self.parent.push(index);
+        self.rank.push(0);
+        self.groups += 1;
+        index
+    }
+
+    fn root(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        let mut node = index;
+        while self.parent[node] != root {
+            let next = self.parent[node];
+            self.parent[node] = root;
+            node = next;
+        }
+        root
+    }
+}
+
+impl<K> Default for DisjointSet<K>
+where
+    K: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_and_find() {
+        let mut set = DisjointSet::new();
+        assert!(set.insert("a"));
+        assert!(!set.insert("a"));
+        assert!(set.union("a", "b"));
+        assert!(set.union("c", "d"));
+        assert!(set.union("b", "d"));
+        assert!(!set.union("a", "c"));
+        set.insert("e");
+
+        assert_eq!(set.len(), 5);
+        assert_eq!(set.group_count(), 2);
+        assert!(set.same_group(&"a", &"d"));
+        assert!(!set.same_group(&"a", &"e"));
+        let root = set.find(&"b").copied();
+        assert_eq!(set.find(&"c").copied(), root);
+        assert_eq!(set.find(&"e"), Some(&"e"));
+        assert_eq!(set.find(&"z"), None);
+    }
+
+    #[test]
+    fn test_groups() {
+        let mut set = DisjointSet::new();
+        for n in 0..10 {
+            set.union(n, n % 3);
+        }
+
+        let mut groups: Vec<Vec<i32>> = set
+            .groups()
+            .map(|group| group.into_iter().copied().collect())
+            .collect();
+        groups.sort();
+        assert_eq!(groups, vec![vec![0, 3, 6, 9], vec![1, 4, 7], vec![2, 5, 8]]);
+    }
+}
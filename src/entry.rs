@@ -0,0 +1,212 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    mem,
+};
+
+use crate::HashTable;
+
+/// A view into a single slot of a [`HashTable`], found with one probe.
+pub enum Entry<'a, K, V, S = RandomState> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+pub struct OccupiedEntry<'a, K, V, S = RandomState> {
+    table: &'a mut HashTable<K, V, S>,
+    index: usize,
+}
+
+pub struct VacantEntry<'a, K, V, S = RandomState> {
+    table: &'a mut HashTable<K, V, S>,
+    hash: u64,
+    key: K,
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let hash = self.make_hash(&key);
+        match self.find_slot_hashed(hash, |stored| *stored == key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { table: self, index }),
+            None => Entry::Vacant(VacantEntry {
+                table: self,
+                hash,
+                key,
+            }),
+        }
+    }
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(self, default: impl FnOnce() -> V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Like [`or_insert_with`](Self::or_insert_with), but `default` gets the
+    /// key the entry was looked up with.
+    pub fn or_insert_with_key(self, default: impl FnOnce(&K) -> V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(&entry.key);
+                entry.insert(value)
+            }
+        }
+    }
+
+    /// Runs `f` on the value if the entry is occupied.
+    pub fn and_modify(mut self, f: impl FnOnce(&mut V)) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+    V: Default,
+{
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        &self.table.slots[self.index].as_ref().unwrap().key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.table.slots[self.index].as_ref().unwrap().value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.table.slots[self.index].as_mut().unwrap().value_mut()
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.table.slots[self.index].as_mut().unwrap().value_mut()
+    }
+
+    /// Replaces the value, returning the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        let bucket = self.table.remove_at(self.index);
+        self.table.shadow.verify(self.table);
+        bucket.value
+    }
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let index = self.table.insert_new(self.hash, self.key, value);
+        self.table.shadow.verify(self.table);
+        &mut self.table.slots[index].as_mut().unwrap().value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_count() {
+        let mut counts: HashTable<&str, u32> = HashTable::new();
+        for word in "a b a c b a".split(' ') {
+            counts.entry(word).and_modify(|c| *c += 1).or_insert(1);
+        }
+
+        assert_eq!(counts.get(&"a"), Some(&3));
+        assert_eq!(counts.get(&"b"), Some(&2));
+        assert_eq!(counts.get(&"c"), Some(&1));
+    }
+
+    #[test]
+    fn test_vacant_insert_survives_resize() {
+        let mut table: HashTable<u32, Vec<u32>> = HashTable::new();
+        for n in 0..100 {
+            table.entry(n % 40).or_default().push(n);
+        }
+
+        assert_eq!(table.get(&3), Some(&vec![3, 43, 83]));
+        assert_eq!(table.get(&39), Some(&vec![39, 79]));
+    }
+
+    #[test]
+    fn test_occupied_entry() {
+        let mut table: HashTable<&str, i32> = HashTable::new();
+        table.insert("x", 1);
+
+        match table.entry("x") {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), &"x");
+                assert_eq!(entry.insert(5), 1);
+                assert_eq!(entry.remove(), 5);
+            }
+            Entry::Vacant(_) => panic!("expected an occupied entry"),
+        }
+        assert_eq!(table.get(&"x"), None);
+
+        let entry = table.entry("y");
+        assert!(matches!(entry, Entry::Vacant(_)));
+        assert_eq!(*entry.or_insert_with(|| 7), 7);
+    }
+
+    #[test]
+    fn test_or_insert_with_key() {
+        let mut lengths: HashTable<String, usize> = HashTable::new();
+        for word in ["apple", "fig", "apple"] {
+            let len = lengths
+                .entry(word.to_string())
+                .or_insert_with_key(|key| key.len());
+            *len += 100;
+        }
+
+        assert_eq!(lengths.get("apple"), Some(&205));
+        assert_eq!(lengths.get("fig"), Some(&103));
+    }
+}
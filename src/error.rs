@@ -0,0 +1,52 @@
+use std::fmt;
+
+use crate::IntegrityError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The requested number of slots does not fit in the address space.
+    CapacityOverflow,
+    /// The allocator could not provide memory for the slot storage.
+    AllocError,
+    /// A table's internal invariants do not hold.
+    Corruption(IntegrityError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::CapacityOverflow => f.write_str("capacity overflow"),
+            Error::AllocError => f.write_str("memory allocation failed"),
+            Error::Corruption(error) => write!(f, "table is corrupt: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Corruption(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<IntegrityError> for Error {
+    fn from(error: IntegrityError) -> Self {
+        Error::Corruption(error)
+    }
+}
+
+/// Two entries with equal keys were found where keys must be unique.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKeyError<K> {
+    pub key: K,
+}
+
+impl<K: fmt::Debug> fmt::Display for DuplicateKeyError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate key {:?}", self.key)
+    }
+}
+
+impl<K: fmt::Debug> std::error::Error for DuplicateKeyError<K> {}
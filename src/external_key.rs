@@ -0,0 +1,217 @@
+//! Tables over keys that implement neither `Hash` nor `Eq`, such as types
+//! from foreign bindings.
+
+use std::{
+    hash::{BuildHasherDefault, Hash, Hasher},
+    sync::Arc,
+};
+
+use crate::{HashTable, RawEntryMut};
+
+type HashFn<K> = dyn Fn(&K) -> u64 + Send + Sync;
+type EqFn<K> = dyn Fn(&K, &K) -> bool + Send + Sync;
+
+/// How an [`ExternalKeyTable`] hashes and compares its keys.
+///
+/// Keys that compare equal must hash equally. The hash is used to pick
+/// slots directly, so it should spread its low bits well.
+pub struct KeyHasher<K> {
+    hash: Box<HashFn<K>>,
+    eq: Box<EqFn<K>>,
+}
+
+impl<K> KeyHasher<K> {
+    pub fn new(
+        hash: impl Fn(&K) -> u64 + Send + Sync + 'static,
+        eq: impl Fn(&K, &K) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            hash: Box::new(hash),
+            eq: Box::new(eq),
+        }
+    }
+}
+
+/// A key stored with its precomputed hash and the strategy that compares
+/// it, so that the inner table's `Hash` and `Eq` bounds hold.
+struct Keyed<K> {
+    key: K,
+    hash: u64,
+    strategy: Arc<KeyHasher<K>>,
+}
+
+impl<K> PartialEq for Keyed<K> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.strategy.eq)(&self.key, &other.key)
+    }
+}
+
+impl<K> Eq for Keyed<K> {}
+
+impl<K> Hash for Keyed<K> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+/// Passes the strategy's hash through unchanged.
+#[derive(Default)]
+struct Precomputed(u64);
+
+impl Hasher for Precomputed {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ u64::from(byte);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = n;
+    }
+}
+
+/// A table whose keys are hashed and compared by a [`KeyHasher`] given at
+/// construction instead of by `Hash` and `Eq` impls.
+pub struct ExternalKeyTable<K, V> {
+    table: HashTable<Keyed<K>, V, BuildHasherDefault<Precomputed>>,
+    strategy: Arc<KeyHasher<K>>,
+}
+
+impl<K, V> ExternalKeyTable<K, V> {
+    pub fn new(strategy: KeyHasher<K>) -> Self {
+        Self {
+            table: HashTable::with_hasher(BuildHasherDefault::default()),
+            strategy: Arc::new(strategy),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts `value` under `key` and returns the value it displaced, if
+    /// any. An existing entry keeps its original key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = (self.strategy.hash)(&key);
+        let eq = &self.strategy.eq;
+        match self
+            .table
+            .raw_entry_mut()
+            .from_hash(hash, |stored| eq(&stored.key, &key))
+        {
+            RawEntryMut::Occupied(mut entry) => Some(entry.insert(value)),
+            RawEntryMut::Vacant(entry) => {
+                let strategy = Arc::clone(&self.strategy);
+                entry.insert(
+                    Keyed {
+                        key,
+                        hash,
+                        strategy,
+                    },
+                    value,
+                );
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let hash = (self.strategy.hash)(key);
+        let (_, value) = self
+            .table
+            .raw_entry()
+            .from_hash(hash, |stored| (self.strategy.eq)(&stored.key, key))?;
+        Some(value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        match self.entry_for(key) {
+            RawEntryMut::Occupied(entry) => Some(entry.into_mut()),
+            RawEntryMut::Vacant(_) => None,
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        match self.entry_for(key) {
+            RawEntryMut::Occupied(entry) => Some(entry.remove()),
+            RawEntryMut::Vacant(_) => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.table.iter().map(|(keyed, value)| (&keyed.key, value))
+    }
+
+    fn entry_for(
+        &mut self,
+        key: &K,
+    ) -> RawEntryMut<'_, Keyed<K>, V, BuildHasherDefault<Precomputed>> {
+        let hash = (self.strategy.hash)(key);
+        let eq = &self.strategy.eq;
+        self.table
+            .raw_entry_mut()
+            .from_hash(hash, |stored| eq(&stored.key, key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stands in for a foreign type: no `Hash`, no `Eq`.
+    struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    fn bitwise() -> KeyHasher<Point> {
+        KeyHasher::new(
+            |p: &Point| {
+                (p.x.to_bits() ^ p.y.to_bits().rotate_left(32)).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            },
+            |a: &Point, b: &Point| a.x.to_bits() == b.x.to_bits() && a.y.to_bits() == b.y.to_bits(),
+        )
+    }
+
+    #[test]
+    fn test_external_key_table() {
+        let mut table = ExternalKeyTable::new(bitwise());
+        for n in 0..200 {
+            let n = f64::from(n);
+            assert_eq!(table.insert(Point { x: n, y: -n }, n), None);
+        }
+        assert_eq!(table.insert(Point { x: 3.0, y: -3.0 }, 30.0), Some(3.0));
+        assert_eq!(table.len(), 200);
+
+        assert_eq!(table.get(&Point { x: 3.0, y: -3.0 }), Some(&30.0));
+        assert!(!table.contains_key(&Point { x: 3.0, y: 3.0 }));
+        *table.get_mut(&Point { x: 7.0, y: -7.0 }).unwrap() += 0.5;
+        assert_eq!(table.remove(&Point { x: 7.0, y: -7.0 }), Some(7.5));
+        assert_eq!(table.remove(&Point { x: 7.0, y: -7.0 }), None);
+        assert_eq!(table.iter().count(), 199);
+        table.table.check_integrity().unwrap();
+    }
+
+    #[test]
+    fn test_colliding_hashes_use_strategy_eq() {
+        let strategy = KeyHasher::new(|_: &Point| 0, |a: &Point, b: &Point| a.x == b.x);
+        let mut table = ExternalKeyTable::new(strategy);
+        table.insert(Point { x: 1.0, y: 0.0 }, "one");
+        table.insert(Point { x: 2.0, y: 0.0 }, "two");
+
+        assert_eq!(table.get(&Point { x: 2.0, y: 9.0 }), Some(&"two"));
+        assert_eq!(table.len(), 2);
+    }
+}
@@ -0,0 +1,186 @@
+//! An append-only table that accepts inserts through `&self`.
+
+use std::{
+    borrow::Borrow,
+    cell::{OnceCell, RefCell},
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::HashTable;
+
+const SEGMENTS: usize = usize::BITS as usize;
+
+/// Allocated on first use and never reallocated.
+type Segment<K, V> = OnceCell<Box<[OnceCell<(K, V)>]>>;
+
+/// A table whose entries, once inserted, are never moved, replaced or
+/// removed, so `insert` can take `&self` and the returned references live
+/// as long as the table. Handy for caches inside otherwise immutable
+/// structures.
+///
+/// Entries go into segments of doubling size that are allocated once and
+/// never reallocated; only the hash index, which stores positions, is
+/// rehashed as the table grows. Not `Sync`. Panics if `K`'s `Eq` or `Hash`
+/// impl inserts into the same table.
+pub struct FrozenTable<K, V, S = RandomState> {
+    segments: [Segment<K, V>; SEGMENTS],
+    indices: RefCell<HashTable<usize, (), S>>,
+}
+
+impl<K, V> FrozenTable<K, V, RandomState>
+where
+    K: Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K, V, S> FrozenTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            segments: std::array::from_fn(|_| OnceCell::new()),
+            indices: RefCell::new(HashTable::with_hasher(hash_builder)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts `value` under `key` unless the key is already present, and
+    /// returns the value now stored under it. An existing value is kept and
+    /// the new one dropped.
+    pub fn insert(&self, key: K, value: V) -> &V {
+        let mut indices = self.indices.borrow_mut();
+        let hash = indices.make_hash(&key);
+        if let Some(slot) = indices.find_slot_hashed(hash, |&at| self.entry(at).0 == key) {
+            let at = indices.slots[slot].as_ref().unwrap().key;
+            return &self.entry(at).1;
+        }
+
+        let at = indices.len();
+        indices.insert_new(hash, at, ());
+        indices.shadow.verify(&indices);
+        drop(indices);
+
+        let (segment, offset) = locate(at);
+        let cells = self.segments[segment]
+            .get_or_init(|| (0..1 << segment).map(|_| OnceCell::new()).collect());
+        let _ = cells[offset].set((key, value));
+        &self.entry(at).1
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let indices = self.indices.borrow();
+        let hash = indices.make_hash(key);
+        let slot = indices.find_slot_hashed(hash, |&at| self.entry(at).0.borrow() == key)?;
+        let at = indices.slots[slot].as_ref().unwrap().key;
+        Some(&self.entry(at).1)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Entries in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        (0..self.len()).map(|at| {
+            let (key, value) = self.entry(at);
+            (key, value)
+        })
+    }
+
+    /// The entry at insertion position `at`, which must have been filled.
+    fn entry(&self, at: usize) -> &(K, V) {
+        let (segment, offset) = locate(at);
+        self.segments[segment].get().unwrap()[offset].get().unwrap()
+    }
+}
+
+impl<K, V> Default for FrozenTable<K, V, RandomState>
+where
+    K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The segment holding insertion position `at` and the offset within it.
+/// Segment `n` holds `2^n` entries.
+fn locate(at: usize) -> (usize, usize) {
+    let segment = (at + 1).ilog2() as usize;
+    (segment, at + 1 - (1 << segment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Parser {
+        interned: FrozenTable<String, Box<str>>,
+    }
+
+    impl Parser {
+        fn intern(&self, name: &str) -> &str {
+            match self.interned.get(name) {
+                Some(interned) => interned,
+                None => self.interned.insert(name.to_string(), name.into()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_references_outlive_later_inserts() {
+        let parser = Parser {
+            interned: FrozenTable::new(),
+        };
+        let first = parser.intern("alpha");
+        for n in 0..1000 {
+            parser.intern(&format!("name-{n}"));
+        }
+
+        assert_eq!(first, "alpha");
+        assert!(std::ptr::eq(first, parser.intern("alpha")));
+        assert_eq!(parser.interned.len(), 1001);
+        assert_eq!(parser.interned.iter().nth(500).unwrap().0, "name-499");
+    }
+
+    #[test]
+    fn test_insert_keeps_existing_value() {
+        let table = FrozenTable::new();
+        assert_eq!(table.insert("a", 1), &1);
+        assert_eq!(table.insert("a", 2), &1);
+        assert_eq!(table.len(), 1);
+        assert!(table.contains_key("a"));
+        assert_eq!(table.get("b"), None);
+    }
+
+    #[test]
+    fn test_locate() {
+        assert_eq!(locate(0), (0, 0));
+        assert_eq!(locate(1), (1, 0));
+        assert_eq!(locate(2), (1, 1));
+        assert_eq!(locate(3), (2, 0));
+        assert_eq!(locate(6), (2, 3));
+        assert_eq!(locate(7), (3, 0));
+    }
+}
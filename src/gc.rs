@@ -0,0 +1,133 @@
+//! Mark-and-sweep removal for tables whose values refer to each other by
+//! key, such as interpreter environments used as object heaps.
+
+use std::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::HashTable;
+
+/// Passed to the trace callback of [`HashTable::gc_traced`] to mark the
+/// entries a value refers to.
+pub struct Tracer<'a, K, V, S> {
+    table: &'a HashTable<K, V, S>,
+    marked: Vec<bool>,
+    pending: Vec<usize>,
+}
+
+impl<K, V, S> Tracer<'_, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Marks `key`'s entry as reachable, so it is kept and traced in turn.
+    /// Keys that are not in the table are ignored.
+    pub fn visit<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some(index) = self.table.find_slot(key) {
+            self.mark(index);
+        }
+    }
+
+    fn mark(&mut self, index: usize) {
+        if !self.marked[index] {
+            self.marked[index] = true;
+            self.pending.push(index);
+        }
+    }
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Removes every entry whose value `mark` rejects, returning how many
+    /// were removed.
+    pub fn gc(&mut self, mark: impl Fn(&V) -> bool) -> usize {
+        self.gc_traced(mark, |_, _| {})
+    }
+
+    /// Keeps the entries whose value `mark` accepts plus everything
+    /// reachable from them, and removes the rest in one pass. `trace` is
+    /// called once for each reachable value and should
+    /// [`visit`](Tracer::visit) the keys that value refers to.
+    ///
+    /// Returns how many entries were removed. All of `mark` and `trace`
+    /// runs before anything is removed, so if either panics the table is
+    /// unchanged.
+    pub fn gc_traced(
+        &mut self,
+        mark: impl Fn(&V) -> bool,
+        mut trace: impl FnMut(&V, &mut Tracer<'_, K, V, S>),
+    ) -> usize {
+        let mut tracer = Tracer {
+            table: self,
+            marked: vec![false; self.slots.len()],
+            pending: Vec::new(),
+        };
+        for (index, slot) in self.slots.iter().enumerate() {
+            if slot.as_ref().is_some_and(|bucket| mark(&bucket.value)) {
+                tracer.mark(index);
+            }
+        }
+        while let Some(index) = tracer.pending.pop() {
+            trace(&self.slots[index].as_ref().unwrap().value, &mut tracer);
+        }
+
+        let marked = tracer.marked;
+        let removed = self.size - marked.iter().filter(|&&marked| marked).count();
+        if removed > 0 {
+            self.retain_slots(marked);
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Object {
+        Global(Vec<&'static str>),
+        Cons(&'static str, &'static str),
+        Atom,
+    }
+
+    #[test]
+    fn test_gc_traced_keeps_reachable_entries() {
+        let mut heap: HashTable<&str, Object> = HashTable::new();
+        heap.insert("env", Object::Global(vec!["list"]));
+        heap.insert("list", Object::Cons("a", "tail"));
+        heap.insert("tail", Object::Cons("b", "list"));
+        heap.insert("a", Object::Atom);
+        heap.insert("b", Object::Atom);
+        heap.insert("garbage", Object::Cons("b", "cycle"));
+        heap.insert("cycle", Object::Cons("garbage", "missing"));
+
+        let removed = heap.gc_traced(
+            |object| matches!(object, Object::Global(_)),
+            |object, tracer| match object {
+                Object::Global(names) => names.iter().for_each(|name| tracer.visit(name)),
+                Object::Cons(head, tail) => {
+                    tracer.visit(head);
+                    tracer.visit(tail);
+                }
+                Object::Atom => {}
+            },
+        );
+
+        assert_eq!(removed, 2);
+        let mut live: Vec<_> = heap.keys().copied().collect();
+        live.sort_unstable();
+        assert_eq!(live, ["a", "b", "env", "list", "tail"]);
+        heap.check_integrity().unwrap();
+
+        assert_eq!(heap.gc(|object| !matches!(object, Object::Atom)), 2);
+        assert_eq!(heap.len(), 3);
+    }
+}
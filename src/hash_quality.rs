@@ -0,0 +1,135 @@
+//! Measuring how well a hasher spreads a given key set.
+
+use std::hash::{BuildHasher, Hash};
+
+use crate::{slots_for, Probing, DEFAULT_LOAD_FACTOR};
+
+/// The result of [`evaluate_hasher`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HasherReport {
+    pub keys: usize,
+    /// Slot count of a default table holding `keys` entries.
+    pub buckets: usize,
+    /// Pearson's chi-square of the home-slot counts against a uniform
+    /// spread. For a good hasher it lands near `buckets - 1`.
+    pub chi_square: f64,
+    /// Average fraction of output bits that change when one input bit is
+    /// flipped. Ideally 0.5.
+    pub avalanche: f64,
+    /// The worst input/output bit pair: 0 when the output bit flips half
+    /// the time, 1 when it always or never flips.
+    pub avalanche_bias: f64,
+}
+
+impl HasherReport {
+    /// `chi_square` divided by its degrees of freedom, so about 1 for a
+    /// uniform spread whatever the sample size.
+    pub fn chi_square_ratio(&self) -> f64 {
+        self.chi_square / (self.buckets - 1) as f64
+    }
+}
+
+/// Hashes `sample_keys` with `hasher` and reports how evenly they land in
+/// the home slots a default table of that size would use, and how well the
+/// hasher avalanches.
+///
+/// Avalanche is measured by hashing each key followed by a `u64` and
+/// flipping each bit of that `u64` in turn. Duplicate keys count as
+/// collisions, so the sample should hold distinct keys.
+///
+/// Panics if `sample_keys` is empty.
+pub fn evaluate_hasher<'a, K, S>(
+    sample_keys: impl IntoIterator<Item = &'a K>,
+    hasher: &S,
+) -> HasherReport
+where
+    K: Hash + ?Sized + 'a,
+    S: BuildHasher,
+{
+    let keys: Vec<&K> = sample_keys.into_iter().collect();
+    assert!(!keys.is_empty(), "cannot evaluate a hasher on no keys");
+
+    let buckets = slots_for(keys.len(), DEFAULT_LOAD_FACTOR);
+    let mut counts = vec![0usize; buckets];
+    let mut flips = [[0usize; 64]; 64];
+
+    for key in &keys {
+        let (home, _) = Probing::Linear.homes(hasher.hash_one(key), buckets);
+        counts[home] += 1;
+
+        let base = hasher.hash_one((key, 0u64));
+        for (input_bit, flips) in flips.iter_mut().enumerate() {
+            let changed = base ^ hasher.hash_one((key, 1u64 << input_bit));
+            for (output_bit, count) in flips.iter_mut().enumerate() {
+                *count += (changed >> output_bit & 1) as usize;
+            }
+        }
+    }
+
+    let expected = keys.len() as f64 / buckets as f64;
+    let chi_square = counts
+        .iter()
+        .map(|&count| (count as f64 - expected).powi(2) / expected)
+        .sum();
+
+    let trials = keys.len() as f64;
+    let rates = flips.iter().flatten().map(|&count| count as f64 / trials);
+    let avalanche = rates.clone().sum::<f64>() / (64.0 * 64.0);
+    let avalanche_bias = rates
+        .map(|rate| (rate - 0.5).abs() * 2.0)
+        .fold(0.0, f64::max);
+
+    HasherReport {
+        keys: keys.len(),
+        buckets,
+        chi_square,
+        avalanche,
+        avalanche_bias,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        collections::hash_map::RandomState,
+        hash::{BuildHasherDefault, Hasher},
+    };
+
+    /// Sums the input bytes: clusters badly and barely avalanches.
+    #[derive(Default)]
+    struct ByteSum(u64);
+
+    impl Hasher for ByteSum {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0 += bytes.iter().map(|&byte| u64::from(byte)).sum::<u64>();
+        }
+    }
+
+    #[test]
+    fn test_good_hasher_scores_well() {
+        let keys: Vec<String> = (0..5000).map(|n| format!("user-{n}")).collect();
+        let report = evaluate_hasher(&keys, &RandomState::new());
+
+        assert_eq!(report.keys, 5000);
+        assert_eq!(report.buckets, slots_for(5000, DEFAULT_LOAD_FACTOR));
+        assert!((0.8..1.2).contains(&report.chi_square_ratio()));
+        assert!((0.45..0.55).contains(&report.avalanche));
+        assert!(report.avalanche_bias < 0.2);
+    }
+
+    #[test]
+    fn test_weak_hasher_scores_badly() {
+        let keys: Vec<String> = (0..5000).map(|n| format!("user-{n}")).collect();
+        let report = evaluate_hasher(&keys, &BuildHasherDefault::<ByteSum>::default());
+
+        assert!(report.chi_square_ratio() > 10.0);
+        assert!(report.avalanche < 0.1);
+        assert_eq!(report.avalanche_bias, 1.0);
+    }
+}
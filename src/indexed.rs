@@ -0,0 +1,278 @@
+use std::{
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash},
+    mem,
+};
+
+use crate::{Bucket, HashTable};
+
+/// A table that remembers insertion order.
+///
+/// Entries live in a dense `Vec` in the order they were first inserted; the
+/// hash slots only hold positions into it. Iteration follows that order and
+/// entries can be addressed by position. [`swap_remove`](Self::swap_remove)
+/// keeps removal O(1) by moving the last entry into the gap.
+pub struct IndexHashTable<K, V, S = RandomState> {
+    entries: Vec<Bucket<K, V>>,
+    indices: HashTable<usize, (), S>,
+}
+
+impl<K, V> IndexHashTable<K, V, RandomState>
+where
+    K: Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K, V, S> IndexHashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            entries: Vec::new(),
+            indices: HashTable::with_hasher(hash_builder),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Inserts `value` under `key` and returns the value it displaced. A
+    /// key that is already present keeps its position.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.indices.make_hash(&key);
+        if let Some(slot) = self.slot_of(hash, &key) {
+            let index = self.index_at(slot);
+            return Some(mem::replace(&mut self.entries[index].value, value));
+        }
+
+        self.indices.insert_new(hash, self.entries.len(), ());
+        self.entries.push(Bucket::new(hash, key, value));
+        self.indices.shadow.verify(&self.indices);
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        Some(&self.entries[index].value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        Some(&mut self.entries[index].value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_index_of(key).is_some()
+    }
+
+    /// The entry at insertion position `index`.
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        let bucket = self.entries.get(index)?;
+        Some((&bucket.key, &bucket.value))
+    }
+
+    /// The insertion position of `key`.
+    pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.slot_of(self.indices.make_hash(key), key)?;
+        Some(self.index_at(slot))
+    }
+
+    /// Removes `key` and moves the last entry into its position, so this is
+    /// O(1) but does not preserve the order of the moved entry.
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.slot_of(self.indices.make_hash(key), key)?;
+        let index = self.indices.remove_at(slot).key;
+        let last = self.entries.len() - 1;
+        let removed = self.entries.swap_remove(index);
+
+        if index != last {
+            let moved = self
+                .indices
+                .find_slot_hashed(self.entries[index].hash, |&stored| stored == last)
+                .unwrap();
+            self.indices.slots[moved].as_mut().unwrap().key = index;
+        }
+
+        self.indices.shadow.verify(&self.indices);
+        Some(removed.value)
+    }
+
+    /// Entries in insertion order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> + ExactSizeIterator {
+        self.entries
+            .iter()
+            .map(|bucket| (&bucket.key, &bucket.value))
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.entries.iter().map(|bucket| &bucket.key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator {
+        self.entries.iter().map(|bucket| &bucket.value)
+    }
+
+    fn slot_of<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.indices
+            .find_slot_hashed(hash, |&index| self.entries[index].key.borrow() == key)
+    }
+
+    fn index_at(&self, slot: usize) -> usize {
+        self.indices.slots[slot].as_ref().unwrap().key
+    }
+}
+
+impl<K, V> Default for IndexHashTable<K, V, RandomState>
+where
+    K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for IndexHashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut table = Self::with_hasher(S::default());
+        table.extend(iter);
+        table
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for IndexHashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, S> fmt::Debug for IndexHashTable<K, V, S>
+where
+    K: Eq + Hash + fmt::Debug,
+    V: fmt::Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Tables are equal when they hold equal entries in the same order.
+impl<K, V, S> PartialEq for IndexHashTable<K, V, S>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<K, V, S> Eq for IndexHashTable<K, V, S>
+where
+    K: Eq + Hash,
+    V: Eq,
+    S: BuildHasher,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insertion_order() {
+        let mut table = IndexHashTable::new();
+        for name in ["delta", "alpha", "charlie", "bravo"] {
+            table.insert(name.to_string(), name.len());
+        }
+        assert_eq!(table.insert("alpha".to_string(), 0), Some(5));
+
+        let keys: Vec<&str> = table.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["delta", "alpha", "charlie", "bravo"]);
+        assert_eq!(table.get_index_of("charlie"), Some(2));
+        assert_eq!(table.get_index(1), Some((&"alpha".to_string(), &0)));
+        assert_eq!(table.get_index(4), None);
+        assert_eq!(
+            format!("{table:?}"),
+            r#"{"delta": 5, "alpha": 0, "charlie": 7, "bravo": 5}"#
+        );
+    }
+
+    #[test]
+    fn test_swap_remove() {
+        let mut table: IndexHashTable<u32, u32> = (0..100).map(|n| (n, n * 10)).collect();
+
+        assert_eq!(table.swap_remove(&10), Some(100));
+        assert_eq!(table.swap_remove(&10), None);
+        assert_eq!(table.get_index(10), Some((&99, &990)));
+        assert_eq!(table.get_index_of(&99), Some(10));
+
+        assert_eq!(table.swap_remove(&98), Some(980));
+        assert_eq!(table.len(), 98);
+        for n in (0..98).filter(|&n| n != 10) {
+            assert_eq!(table.get(&n), Some(&(n * 10)));
+            let index = table.get_index_of(&n).unwrap();
+            assert_eq!(table.get_index(index).map(|(key, _)| *key), Some(n));
+        }
+        table.indices.check_integrity().unwrap();
+    }
+
+    #[test]
+    fn test_equality_follows_order() {
+        let forward: IndexHashTable<u32, u32> = (0..5).map(|n| (n, n)).collect();
+        let backward: IndexHashTable<u32, u32> = (0..5).rev().map(|n| (n, n)).collect();
+        let mut again: IndexHashTable<u32, u32> = (0..5).map(|n| (n, n)).collect();
+
+        assert_eq!(forward, again);
+        assert_ne!(forward, backward);
+        *again.get_mut(&3).unwrap() = 30;
+        assert_ne!(forward, again);
+    }
+}
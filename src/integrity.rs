@@ -0,0 +1,138 @@
+use std::{
+    fmt,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::HashTable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// The stored size does not match the number of occupied slots.
+    SizeMismatch { recorded: usize, occupied: usize },
+    /// The key in `slot` cannot be found by probing from its home bucket.
+    UnreachableKey { slot: usize },
+    /// The same key is stored in two slots.
+    DuplicateKey { first: usize, second: usize },
+    /// The hash cached in `slot` is not the hash of the key stored there.
+    /// Only reported by [`HashTable::from_raw_parts`]: tables built
+    /// through the raw entry API may cache hashes of something other than
+    /// the key, so [`check_integrity`](HashTable::check_integrity) does not
+    /// rehash keys.
+    HashMismatch { slot: usize },
+    /// [`HashTable::from_raw_parts`] was given no slots.
+    NoSlots,
+    /// [`HashTable::from_raw_parts`] was given a load factor that is not
+    /// strictly between 0 and 1.
+    InvalidLoadFactor,
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::SizeMismatch { recorded, occupied } => {
+                write!(f, "size is {recorded} but {occupied} slots are occupied")
+            }
+            IntegrityError::UnreachableKey { slot } => {
+                write!(f, "key in slot {slot} is unreachable from its home bucket")
+            }
+            IntegrityError::DuplicateKey { first, second } => {
+                write!(f, "slots {first} and {second} hold the same key")
+            }
+            IntegrityError::HashMismatch { slot } => {
+                write!(f, "cached hash in slot {slot} does not match its key")
+            }
+            IntegrityError::NoSlots => write!(f, "table has no slots"),
+            IntegrityError::InvalidLoadFactor => {
+                write!(f, "load factor must be between 0 and 1 (exclusive)")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Verifies the table's internal invariants. Intended for debugging and
+    /// tests; it probes for every stored key, so it is not cheap.
+    pub fn check_integrity(&self) -> Result<(), IntegrityError> {
+        let occupied = self.slots.iter().filter(|slot| slot.is_some()).count();
+        if occupied != self.size {
+            return Err(IntegrityError::SizeMismatch {
+                recorded: self.size,
+                occupied,
+            });
+        }
+
+        for (slot, entry) in self.slots.iter().enumerate() {
+            let Some(bucket) = entry else {
+                continue;
+            };
+
+            match self.locate(bucket.hash, |key| *key == bucket.key).0 {
+                None => return Err(IntegrityError::UnreachableKey { slot }),
+                Some(first) if first != slot => {
+                    return Err(IntegrityError::DuplicateKey {
+                        first,
+                        second: slot,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_integrity_ok() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        for i in 0..100 {
+            table.insert(i, i);
+        }
+        assert_eq!(table.check_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn test_check_integrity_detects_corruption() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        table.insert(1, 1);
+        table.size = 2;
+        assert_eq!(
+            table.check_integrity(),
+            Err(IntegrityError::SizeMismatch {
+                recorded: 2,
+                occupied: 1
+            })
+        );
+
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        table.insert(1, 1);
+        let home = table.hash(&1);
+        let slot = (home + 1) % table.slots.len();
+        table.slots[slot] = table.slots[home].clone();
+        table.size = 2;
+        assert_eq!(
+            table.check_integrity(),
+            Err(IntegrityError::DuplicateKey {
+                first: home,
+                second: slot
+            })
+        );
+
+        table.slots[home] = None;
+        table.size = 1;
+        assert_eq!(
+            table.check_integrity(),
+            Err(IntegrityError::UnreachableKey { slot })
+        );
+    }
+}
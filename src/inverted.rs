@@ -0,0 +1,192 @@
+//! Builds a token-to-documents inverted index on top of [`HashTable`].
+
+use crate::HashTable;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Posting {
+    pub doc: u64,
+    /// Token positions within the document; empty unless the index was
+    /// built with positions enabled.
+    pub positions: Vec<u32>,
+}
+
+/// Maps each token to the list of documents it occurs in.
+///
+/// Documents are fed in as `(document_id, tokens)` pairs. Posting lists are
+/// appended to as documents arrive, so they stay sorted as long as document
+/// ids are increasing; [`compact`](Self::compact) restores order and merges
+/// repeated documents otherwise.
+pub struct InvertedIndex {
+    postings: HashTable<String, Vec<Posting>>,
+    positional: bool,
+}
+
+impl InvertedIndex {
+    pub fn new() -> Self {
+        Self {
+            postings: HashTable::new(),
+            positional: false,
+        }
+    }
+
+    /// An index that also records where in each document a token occurs.
+    pub fn with_positions() -> Self {
+        Self {
+            positional: true,
+            ..Self::new()
+        }
+    }
+
+    /// Number of distinct tokens.
+    pub fn len(&self) -> usize {
+        self.postings.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn add_document<I, T>(&mut self, doc: u64, tokens: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let positional = self.positional;
+        for (position, token) in tokens.into_iter().enumerate() {
+            let postings = self.postings_mut(token.as_ref());
+
+            let posting = match postings.last_mut() {
+                Some(last) if last.doc == doc => last,
+                _ => {
+                    postings.push(Posting {
+                        doc,
+                        positions: Vec::new(),
+                    });
+                    postings.last_mut().unwrap()
+                }
+            };
+            if positional {
+                posting.positions.push(position as u32);
+            }
+        }
+    }
+
+    /// Builds an index from a stream of `(document_id, tokens)` pairs.
+    pub fn build<D, I, T>(documents: D, positional: bool) -> Self
+    where
+        D: IntoIterator<Item = (u64, I)>,
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut index = if positional {
+            Self::with_positions()
+        } else {
+            Self::new()
+        };
+        for (doc, tokens) in documents {
+            index.add_document(doc, tokens);
+        }
+        index.compact();
+        index
+    }
+
+    pub fn postings(&self, token: &str) -> &[Posting] {
+        self.postings
+            .get(token)
+            .map_or(&[], |postings| postings.as_slice())
+    }
+
+    /// Ids of the documents containing `token`.
+    pub fn documents<'a>(&'a self, token: &str) -> impl Iterator<Item = u64> + 'a {
+        self.postings(token).iter().map(|posting| posting.doc)
+    }
+
+    /// Sorts every posting list by document id, merges postings for the same
+    /// document, and releases spare capacity.
+    pub fn compact(&mut self) {
+        for slot in self.postings.slots.iter_mut().flatten() {
+            let postings = &mut slot.value;
+            postings.sort_by_key(|posting| posting.doc);
+            postings.dedup_by(|next, kept| {
+                if next.doc != kept.doc {
+                    return false;
+                }
+                kept.positions.append(&mut next.positions);
+                true
+            });
+            for posting in postings.iter_mut() {
+                posting.positions.sort_unstable();
+                posting.positions.shrink_to_fit();
+            }
+            postings.shrink_to_fit();
+        }
+    }
+
+    fn postings_mut(&mut self, token: &str) -> &mut Vec<Posting> {
+        if self.postings.find_slot(token).is_none() {
+            self.postings.insert(token.to_string(), Vec::new());
+        }
+        let index = self.postings.find_slot(token).unwrap();
+        &mut self.postings.slots[index].as_mut().unwrap().value
+    }
+}
+
+impl Default for InvertedIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<&str> {
+        text.split_whitespace().collect()
+    }
+
+    #[test]
+    fn test_build_index() {
+        let index = InvertedIndex::build(
+            [
+                (1, tokens("the quick brown fox")),
+                (2, tokens("the lazy dog")),
+                (3, tokens("quick quick dog")),
+            ],
+            false,
+        );
+
+        assert_eq!(index.len(), 6);
+        assert_eq!(index.documents("the").collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(index.documents("quick").collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(index.documents("dog").collect::<Vec<_>>(), vec![2, 3]);
+        assert!(index.postings("cat").is_empty());
+        assert!(index.postings("quick")[1].positions.is_empty());
+    }
+
+    #[test]
+    fn test_positions_and_compaction() {
+        let mut index = InvertedIndex::with_positions();
+        index.add_document(7, tokens("a b a"));
+        index.add_document(3, tokens("b"));
+        index.add_document(7, tokens("b"));
+
+        assert_eq!(index.documents("b").collect::<Vec<_>>(), vec![7, 3, 7]);
+
+        index.compact();
+        assert_eq!(
+            index.postings("b"),
+            &[
+                Posting {
+                    doc: 3,
+                    positions: vec![0],
+                },
+                Posting {
+                    doc: 7,
+                    positions: vec![0, 1],
+                },
+            ]
+        );
+        assert_eq!(index.postings("a")[0].positions, vec![0, 2]);
+    }
+}
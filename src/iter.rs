@@ -0,0 +1,479 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    iter::FusedIterator,
+    mem, slice, vec,
+};
+
+use crate::{
+    empty_slots, slots_for, Bucket, DuplicateKeyError, DuplicatePolicy, HashTable, Shadow,
+};
+
+/// Borrowing iterator over a table's entries, in slot order.
+pub struct Iter<'a, K, V> {
+    slots: slice::Iter<'a, Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bucket = self.slots.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some((&bucket.key, &bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+/// Iterator over a table's entries with mutable access to the values.
+pub struct IterMut<'a, K, V> {
+    slots: slice::IterMut<'a, Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bucket = self.slots.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some(bucket.key_value_mut())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+/// Owning iterator over a table's entries.
+pub struct IntoIter<K, V> {
+    slots: vec::IntoIter<Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bucket = self.slots.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some((bucket.key, bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+/// Owning iterator over the entries removed by
+/// [`HashTable::drain`].
+pub struct Drain<K, V> {
+    inner: IntoIter<K, V>,
+}
+
+impl<K, V> Iterator for Drain<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Drain<K, V> {}
+impl<K, V> FusedIterator for Drain<K, V> {}
+
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+pub struct ValuesMut<'a, K, V> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Visits every entry in slot order, which is unrelated to insertion
+    /// order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            slots: self.slots.iter(),
+            remaining: self.size,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            slots: self.slots.iter_mut(),
+            remaining: self.size,
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    /// Empties the table up front, keeping its slot count, and yields the
+    /// removed entries. Entries the iterator is dropped before reaching are
+    /// dropped with it.
+    pub fn drain(&mut self) -> Drain<K, V> {
+        let slot_count = self.slots.len();
+        let slots = mem::replace(&mut self.slots, empty_slots(slot_count));
+        let remaining = mem::take(&mut self.size);
+        self.generation += 1;
+        self.shadow = Shadow::new();
+
+        Drain {
+            inner: IntoIter {
+                slots: slots.into_iter(),
+                remaining,
+            },
+        }
+    }
+}
+
+impl<K, V> HashTable<K, V, RandomState>
+where
+    K: Eq + Hash,
+{
+    /// Collects `iter` into a table, failing on the first key that appears
+    /// twice instead of letting the later value win.
+    pub fn try_from_iter<I>(iter: I) -> Result<Self, DuplicateKeyError<K>>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        Self::from_iter_with_policy(iter, DuplicatePolicy::Reject)
+    }
+
+    /// Collects `iter` into a table pre-sized from its size hint, resolving
+    /// repeated keys with `policy`.
+    pub fn from_iter_with_policy<I>(
+        iter: I,
+        policy: DuplicatePolicy<'_, V>,
+    ) -> Result<Self, DuplicateKeyError<K>>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut table = Self::new();
+        table.extend_with_policy(iter, policy)?;
+        Ok(table)
+    }
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Inserts every pair in one pass, resolving keys that are already
+    /// present (or repeated within `iter`) with `policy`. The table grows
+    /// once up front for the iterator's size hint.
+    ///
+    /// Under [`DuplicatePolicy::Reject`] the first duplicate stops the load;
+    /// pairs before it stay inserted.
+    pub fn extend_with_policy<I>(
+        &mut self,
+        iter: I,
+        mut policy: DuplicatePolicy<'_, V>,
+    ) -> Result<(), DuplicateKeyError<K>>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let iter = iter.into_iter();
+        let slot_count = slots_for(self.size + iter.size_hint().0, self.load_factor);
+        if slot_count > self.slots.len() {
+            self.rehash_into(empty_slots(slot_count));
+        }
+
+        for (key, value) in iter {
+            let hash = self.make_hash(&key);
+            match self.find_slot_hashed(hash, |stored| *stored == key) {
+                Some(index) => {
+                    let bucket = self.slots[index].as_mut().unwrap();
+                    if !policy.resolve(bucket, value) {
+                        self.shadow.verify(self);
+                        return Err(DuplicateKeyError { key });
+                    }
+                }
+                None => {
+                    self.insert_new(hash, key, value);
+                }
+            }
+        }
+
+        self.shadow.verify(self);
+        Ok(())
+    }
+}
+
+impl<K, V, S> IntoIterator for HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            remaining: self.size,
+            slots: self.slots.into_iter(),
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut table = HashTable::with_hasher(S::default());
+        table.extend(iter);
+        table
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Inserts every pair; later pairs overwrite earlier ones with an equal
+    /// key.
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> HashTable<u32, String> {
+        (0..40).map(|n| (n, n.to_string())).collect()
+    }
+
+    #[test]
+    fn test_borrowing_iterators() {
+        let table = table();
+        assert_eq!(table.iter().len(), 40);
+
+        let mut keys: Vec<u32> = table.keys().copied().collect();
+        keys.sort();
+        assert_eq!(keys, (0..40).collect::<Vec<_>>());
+
+        for (key, value) in &table {
+            assert_eq!(*value, key.to_string());
+        }
+        assert_eq!(table.values().map(|value| value.len()).sum::<usize>(), 70);
+    }
+
+    #[test]
+    fn test_mutating_iterators() {
+        let mut table = table();
+        for value in table.values_mut() {
+            value.push('!');
+        }
+        for (key, value) in &mut table {
+            if key % 2 == 0 {
+                value.clear();
+            }
+        }
+
+        assert_eq!(table.get(&3).map(String::as_str), Some("3!"));
+        assert_eq!(table.get(&4).map(String::as_str), Some(""));
+    }
+
+    #[test]
+    fn test_into_iter_and_extend() {
+        let mut table = table();
+        table.extend([(0, "zero".to_string()), (100, "hundred".to_string())]);
+        assert_eq!(table.get(&0).map(String::as_str), Some("zero"));
+
+        let mut pairs: Vec<(u32, String)> = table.into_iter().collect();
+        pairs.sort();
+        assert_eq!(pairs.len(), 41);
+        assert_eq!(pairs[0], (0, "zero".to_string()));
+        assert_eq!(pairs[40], (100, "hundred".to_string()));
+    }
+
+    #[test]
+    fn test_try_from_iter() {
+        let table = HashTable::try_from_iter((0..100).map(|n| (n, n * 2))).unwrap();
+        assert_eq!(table.iter().len(), 100);
+        assert_eq!(table.get(&99), Some(&198));
+
+        let err = HashTable::try_from_iter([("a", 1), ("b", 2), ("a", 3), ("b", 4)]);
+        assert_eq!(err.err(), Some(DuplicateKeyError { key: "a" }));
+    }
+
+    #[test]
+    fn test_bulk_load_policies() {
+        let rows = || [("a", 1), ("b", 2), ("a", 3), ("c", 4), ("a", 5)];
+
+        let first = HashTable::from_iter_with_policy(rows(), DuplicatePolicy::KeepFirst).unwrap();
+        assert_eq!(first.get("a"), Some(&1));
+        let last = HashTable::from_iter_with_policy(rows(), DuplicatePolicy::KeepLast).unwrap();
+        assert_eq!(last.get("a"), Some(&5));
+        let summed =
+            HashTable::from_iter_with_policy(rows(), DuplicatePolicy::merge(|a, b| *a += b))
+                .unwrap();
+        assert_eq!(summed.get("a"), Some(&9));
+        assert_eq!(summed.iter().len(), 3);
+
+        let mut table = last;
+        let err = table.extend_with_policy([("d", 6), ("b", 7)], DuplicatePolicy::Reject);
+        assert_eq!(err, Err(DuplicateKeyError { key: "b" }));
+        assert_eq!(table.get("d"), Some(&6));
+        assert_eq!(table.get("b"), Some(&2));
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut table = table();
+        let slot_count = table.slots.len();
+
+        let mut drained: Vec<(u32, String)> = table.drain().collect();
+        drained.sort();
+        assert_eq!(drained.len(), 40);
+        assert_eq!(drained[7], (7, "7".to_string()));
+        assert!(table.is_empty());
+        assert_eq!(table.slots.len(), slot_count);
+
+        table.insert(1, "one".to_string());
+        let mut drain = table.drain();
+        assert_eq!(drain.len(), 1);
+        drop(drain.next());
+        assert_eq!(table.get(&1), None);
+    }
+
+    #[cfg(feature = "entry-metadata")]
+    #[test]
+    fn test_extend_with_policy_updates_metadata() {
+        let mut table: HashTable<&str, u32> = [("a", 1), ("b", 1), ("c", 1)].into_iter().collect();
+        let before = table.entry_metadata("a").unwrap();
+        let kept = table.entry_metadata("c").unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        table
+            .extend_with_policy([("a", 2)], DuplicatePolicy::KeepLast)
+            .unwrap();
+        table
+            .extend_with_policy([("b", 2)], DuplicatePolicy::merge(|a, b| *a += b))
+            .unwrap();
+        table
+            .extend_with_policy([("c", 2)], DuplicatePolicy::KeepFirst)
+            .unwrap();
+
+        assert!(table.entry_metadata("a").unwrap().last_modified > before.last_modified);
+        assert!(table.entry_metadata("b").unwrap().last_modified > before.last_modified);
+        assert_eq!(table.entry_metadata("c").unwrap(), kept);
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    collections::HashSet,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::HashTable;
+
+/// Anything that can answer key-membership queries.
+pub trait KeySet<K> {
+    fn contains(&self, key: &K) -> bool;
+}
+
+impl<K, S> KeySet<K> for HashSet<K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn contains(&self, key: &K) -> bool {
+        HashSet::contains(self, key)
+    }
+}
+
+/// Type-erased access to a table's keys, so views don't carry `V`.
+trait SlotKeys<K> {
+    fn slot_count(&self) -> usize;
+    fn key_at(&self, index: usize) -> Option<&K>;
+    fn contains(&self, key: &K) -> bool;
+    fn len(&self) -> usize;
+}
+
+impl<K, V, S> SlotKeys<K> for HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn key_at(&self, index: usize) -> Option<&K> {
+        self.slots[index].as_ref().map(|bucket| &bucket.key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        self.find_slot(key).is_some()
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+}
+
+/// A borrowed, set-like view of a table's keys. Nothing is copied; every
+/// query goes straight to the underlying table.
+pub struct KeySetView<'a, K> {
+    table: &'a (dyn SlotKeys<K> + 'a),
+}
+
+impl<'a, K> KeySetView<'a, K> {
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.table.contains(key)
+    }
+
+    pub fn iter(&self) -> KeySetIter<'a, K> {
+        KeySetIter {
+            table: self.table,
+            index: 0,
+        }
+    }
+
+    /// Keys present in both `self` and `other`.
+    pub fn intersection<'b, S: KeySet<K>>(
+        &'b self,
+        other: &'b S,
+    ) -> impl Iterator<Item = &'a K> + 'b {
+        self.iter().filter(move |key| other.contains(key))
+    }
+
+    /// Keys present in `self` but not in `other`.
+    pub fn difference<'b, S: KeySet<K>>(
+        &'b self,
+        other: &'b S,
+    ) -> impl Iterator<Item = &'a K> + 'b {
+        self.iter().filter(move |key| !other.contains(key))
+    }
+
+    /// Keys present in either view, each yielded once.
+    pub fn union<'b>(&'b self, other: &'b KeySetView<'a, K>) -> impl Iterator<Item = &'a K> + 'b {
+        self.iter().chain(other.difference(self))
+    }
+
+    pub fn is_subset<S: KeySet<K>>(&self, other: &S) -> bool {
+        self.iter().all(|key| other.contains(key))
+    }
+
+    pub fn is_disjoint<S: KeySet<K>>(&self, other: &S) -> bool {
+        self.intersection(other).next().is_none()
+    }
+}
+
+impl<K> KeySet<K> for KeySetView<'_, K> {
+    fn contains(&self, key: &K) -> bool {
+        KeySetView::contains(self, key)
+    }
+}
+
+pub struct KeySetIter<'a, K> {
+    table: &'a (dyn SlotKeys<K> + 'a),
+    index: usize,
+}
+
+impl<'a, K> Iterator for KeySetIter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.table.slot_count() {
+            let key = self.table.key_at(self.index);
+            self.index += 1;
+            if key.is_some() {
+                return key;
+            }
+        }
+        None
+    }
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn key_set(&self) -> KeySetView<'_, K> {
+        KeySetView { table: self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table_of(keys: impl IntoIterator<Item = i32>) -> HashTable<i32, ()> {
+        let mut table = HashTable::new();
+        for key in keys {
+            table.insert(key, ());
+        }
+        table
+    }
+
+    fn sorted<'a>(keys: impl Iterator<Item = &'a i32>) -> Vec<i32> {
+        let mut keys: Vec<i32> = keys.copied().collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_key_set_operations() {
+        let a = table_of(0..6);
+        let b = table_of(4..10);
+        let (a, b) = (a.key_set(), b.key_set());
+
+        assert_eq!(a.len(), 6);
+        assert!(a.contains(&5));
+        assert!(!a.contains(&6));
+        assert_eq!(sorted(a.iter()), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(sorted(a.intersection(&b)), vec![4, 5]);
+        assert_eq!(sorted(a.difference(&b)), vec![0, 1, 2, 3]);
+        assert_eq!(sorted(a.union(&b)), (0..10).collect::<Vec<_>>());
+        assert!(!a.is_disjoint(&b));
+        assert!(!a.is_subset(&b));
+    }
+
+    #[test]
+    fn test_key_set_against_hash_set() {
+        let table = table_of(0..4);
+        let set: HashSet<i32> = (0..10).collect();
+        let view = table.key_set();
+
+        assert!(view.is_subset(&set));
+        assert_eq!(sorted(view.intersection(&set)), vec![0, 1, 2, 3]);
+        assert_eq!(view.difference(&set).count(), 0);
+    }
+}
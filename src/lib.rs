@@ -1,116 +1,858 @@
 #![allow(dead_code)]
 
 use std::{
-    collections::hash_map::DefaultHasher,
-    hash::{Hash, Hasher},
+    alloc::Layout,
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
     mem,
 };
 
+mod adaptive;
+mod adjacency;
+mod builder;
+mod config;
+mod content_store;
+mod cursor;
+mod dense;
+mod diagnostics;
+mod digest;
+mod disjoint_set;
+mod entry;
+mod error;
+mod external_key;
+mod frozen;
+mod gc;
+mod hash_quality;
+mod indexed;
+mod integrity;
+mod inverted;
+mod iter;
+mod key_set;
+mod metadata;
+mod multi_index;
+mod namespaced;
+mod ordered_key;
+mod parts;
+mod probe_check;
+mod raw_entry;
+mod raw_parts;
+mod scan;
+mod secret;
+mod shadow;
+mod sharded;
+mod shared;
+mod slot_index;
+mod sorted_index;
+mod swappable;
+mod timer;
+mod trace;
+mod transform;
+mod windowed;
+
+pub use adaptive::{AdaptiveHashTable, AdaptiveHasher, AdaptiveState};
+pub use adjacency::AdjacencyTable;
+pub use builder::HashTableBuilder;
+pub use config::{ConfigError, ConfigTable};
+pub use content_store::{ContentId, ContentStore};
+pub use cursor::{Cursor, Page};
+pub use dense::DenseMap;
+pub use diagnostics::{BucketInfo, Collision};
+pub use digest::MerkleDigest;
+pub use disjoint_set::DisjointSet;
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use error::{DuplicateKeyError, Error};
+pub use external_key::{ExternalKeyTable, KeyHasher};
+pub use frozen::FrozenTable;
+pub use gc::Tracer;
+pub use hash_quality::{evaluate_hasher, HasherReport};
+pub use indexed::IndexHashTable;
+pub use integrity::IntegrityError;
+pub use inverted::{InvertedIndex, Posting};
+pub use iter::{Drain, IntoIter, Iter, IterMut, Keys, Values, ValuesMut};
+pub use key_set::{KeySet, KeySetIter, KeySetView};
+#[cfg(feature = "entry-metadata")]
+pub use metadata::EntryMetadata;
+pub use multi_index::{IndexHandle, MultiIndexError, MultiIndexTable};
+pub use namespaced::NamespacedTable;
+pub use ordered_key::OrderedKeyTable;
+pub use parts::TablePartMut;
+#[cfg(feature = "probe-check")]
+pub use probe_check::{ProbeAction, ProbeStats};
+pub use raw_entry::{
+    RawEntryBuilder, RawEntryBuilderMut, RawEntryMut, RawOccupiedEntryMut, RawVacantEntryMut,
+};
+pub use raw_parts::RawParts;
+pub use secret::{SecretKey, SecretTable};
+pub use sharded::{ShardedHashTable, ValueRef};
+pub use shared::SharedTable;
+pub use slot_index::SlotIndex;
+pub use sorted_index::SortedIndexTable;
+pub use swappable::{SwappableReader, SwappableTable};
+pub use timer::TimerTable;
+pub use trace::{Op, Recorder, Trace};
+pub use transform::DuplicatePolicy;
+pub use windowed::WindowedTable;
+
+use metadata::Meta;
+use probe_check::ProbeMonitor;
+use shadow::{EntryId, Shadow};
+
 const INITIAL_CAPACITY: usize = 16;
+const DEFAULT_LOAD_FACTOR: f64 = 0.5;
+
+/// How colliding entries are placed and found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Probing {
+    /// Scan forward from the key's home bucket.
+    #[default]
+    Linear,
+    /// Derive a second home bucket from the hash and insert into whichever
+    /// of the two probe chains reaches an empty slot sooner. Lookups scan
+    /// both chains, which keeps the longest chains short at high load
+    /// factors at the cost of slower misses.
+    TwoChoice,
+}
 
-pub struct HashTable<K: Eq + Hash + Clone, V: Clone> {
-    slots: Vec<Option<(K, V)>>,
+impl Probing {
+    /// The slot `hash` maps to in a table of `slot_count` slots, and under
+    /// two-choice probing its alternative slot.
+    fn homes(self, hash: u64, slot_count: usize) -> (usize, Option<usize>) {
+        let primary = hash as usize % slot_count;
+        let second = match self {
+            Probing::Linear => None,
+            Probing::TwoChoice => {
+                let mixed = hash.rotate_left(32).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                Some(mixed as usize % slot_count)
+            }
+        };
+        (primary, second)
+    }
+}
+
+pub struct HashTable<K, V, S = RandomState> {
+    slots: Vec<Option<Bucket<K, V>>>,
     size: usize,
+    load_factor: f64,
+    probing: Probing,
+    /// Mixed into hashes before they are mapped to slots; 0 leaves them
+    /// unchanged.
+    salt: u64,
+    /// Advanced whenever entries may move between slots, invalidating any
+    /// outstanding `SlotIndex`.
+    generation: u64,
+    shadow: Shadow,
+    probes: ProbeMonitor<K>,
+    hash_builder: S,
+}
+
+#[derive(Clone)]
+struct Bucket<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+    meta: Meta,
+    id: EntryId,
 }
 
-impl<K, V> HashTable<K, V>
+impl<K, V> Bucket<K, V> {
+    fn new(hash: u64, key: K, value: V) -> Self {
+        Self {
+            hash,
+            key,
+            value,
+            meta: Meta::new(),
+            id: EntryId::new(),
+        }
+    }
+
+    /// Mutable access to the value, recorded as a modification.
+    fn value_mut(&mut self) -> &mut V {
+        self.meta.touch();
+        &mut self.value
+    }
+
+    /// Like [`value_mut`](Self::value_mut), also lending out the key.
+    fn key_value_mut(&mut self) -> (&K, &mut V) {
+        self.meta.touch();
+        (&self.key, &mut self.value)
+    }
+}
+
+impl<K, V> HashTable<K, V, RandomState>
 where
-    K: Eq + Hash + Clone,
-    V: Clone,
+    K: Eq + Hash,
 {
     pub fn new() -> Self {
-        let slots = vec![None; INITIAL_CAPACITY];
+        Self::with_hasher(RandomState::new())
+    }
 
-        Self { slots, size: 0 }
+    /// An empty table that can hold `capacity` entries without growing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
     }
+}
 
-    pub fn insert(&mut self, key: K, value: V) {
-        if let Some(index) = self.find_slot(&key) {
-            self.slots[index] = Some((key, value));
-            return;
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// An empty table that hashes keys with `hash_builder`.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// An empty table that can hold `capacity` entries without growing and
+    /// hashes keys with `hash_builder`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let slot_count = slots_for(capacity, DEFAULT_LOAD_FACTOR);
+        Self::with_slots(
+            slot_count,
+            DEFAULT_LOAD_FACTOR,
+            Probing::Linear,
+            hash_builder,
+        )
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    /// The salt set with [`HashTableBuilder::salt`].
+    pub fn salt(&self) -> u64 {
+        self.salt
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Number of entries the table can hold before it next grows.
+    pub fn capacity(&self) -> usize {
+        (self.slots.len() as f64 * self.load_factor).ceil() as usize
+    }
+
+    /// Grows the table, if needed, so that `additional` more entries fit
+    /// without any further rehashing.
+    pub fn reserve(&mut self, additional: usize) {
+        let slot_count = slots_for(self.size.saturating_add(additional), self.load_factor);
+        if slot_count > self.slots.len() {
+            self.rehash_into(empty_slots(slot_count));
         }
+    }
 
-        if self.size * 2 >= self.slots.len() {
-            self.resize();
+    /// Shrinks the slot storage to the smallest size that still holds the
+    /// current entries at the table's load factor.
+    pub fn shrink_to_fit(&mut self) {
+        let slot_count = slots_for(self.size, self.load_factor);
+        if slot_count < self.slots.len() {
+            self.rehash_into(empty_slots(slot_count));
         }
+    }
 
-        let mut index = self.hash(&key);
-        let capacity = self.slots.len();
+    /// Removes every entry, keeping the allocated slots.
+    /// The table is emptied even if dropping an entry panics: the remaining
+    /// entries are still dropped and the slots refilled.
+    pub fn clear(&mut self) {
+        let slot_count = self.slots.len();
+        self.size = 0;
+        self.generation += 1;
+        self.shadow = Shadow::new();
 
-        while let Some((_, _)) = &self.slots[index] {
-            index = (index + 1) % capacity;
+        let refill = Refill(&mut self.slots, slot_count);
+        refill.0.clear();
+    }
+
+    /// Inserts `value` under `key` and returns the value it displaced, if
+    /// any. An existing entry keeps its original key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.make_hash(&key);
+        let old = self.insert_hashed(hash, key, value);
+        self.shadow.verify(self);
+        old
+    }
+
+    /// Inserts every pair, as repeated [`insert`](Self::insert) calls would.
+    ///
+    /// The pairs are hashed up front, the table grows at most once, to fit
+    /// just the distinct keys it does not hold yet, and they are placed in
+    /// order of their home slot so that placement walks the slots front to
+    /// back. Pairs with equal keys keep their relative order, so the last
+    /// one wins.
+    pub fn insert_many(&mut self, pairs: impl IntoIterator<Item = (K, V)>) {
+        let mut batch: Vec<(u64, K, V)> = pairs
+            .into_iter()
+            .map(|(key, value)| (self.make_hash(&key), key, value))
+            .collect();
+
+        // Equal keys have equal hashes, so after sorting by hash any repeat
+        // of a key sits in the run of pairs sharing its hash.
+        batch.sort_by_key(|&(hash, _, _)| hash);
+        let new_keys = (0..batch.len())
+            .filter(|&at| {
+                let (hash, key, _) = &batch[at];
+                let repeated = batch[..at]
+                    .iter()
+                    .rev()
+                    .take_while(|(earlier, _, _)| earlier == hash)
+                    .any(|(_, earlier, _)| earlier == key);
+                !repeated && self.locate(*hash, |stored| stored == key).0.is_none()
+            })
+            .count();
+        self.reserve(new_keys);
+        batch.sort_by_key(|&(hash, _, _)| self.index_for(hash));
+
+        for (hash, key, value) in batch {
+            self.insert_hashed(hash, key, value);
         }
+        self.shadow.verify(self);
+    }
 
-        self.slots[index] = Some((key, value));
-        self.size += 1;
+    pub fn insert_from_slice(&mut self, pairs: &[(K, V)])
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.insert_many(pairs.iter().cloned());
     }
 
-    pub fn get(&self, key: &K) -> Option<&V> {
+    /// Like [`insert`](Self::insert), but returns an error instead of
+    /// aborting when growing the table fails.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, Error> {
+        let hash = self.make_hash(&key);
+        let old = if let Some(index) = self.find_slot_hashed(hash, |stored| *stored == key) {
+            let bucket = self.slots[index].as_mut().unwrap();
+            Some(mem::replace(bucket.value_mut(), value))
+        } else {
+            if self.needs_resize() {
+                self.try_rehash(self.slots.len() * 2)?;
+            }
+
+            self.insert_new(hash, key, value);
+            None
+        };
+
+        self.shadow.verify(self);
+        Ok(old)
+    }
+
+    /// Resizes the table to hold at least `capacity` entries (never fewer
+    /// than it currently stores) without growing. On error the table is
+    /// left untouched.
+    pub fn try_resize(&mut self, capacity: usize) -> Result<(), Error> {
+        let slot_count = slots_for(capacity.max(self.size), self.load_factor);
+        self.try_rehash(slot_count)
+    }
+
+    /// Never allocates.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
         if let Some(index) = self.find_slot(key) {
-            Some(&self.slots[index].as_ref().unwrap().1)
+            let bucket = self.slots[index].as_ref().unwrap();
+            bucket.meta.access();
+            Some(&bucket.value)
         } else {
             None
         }
     }
 
-    pub fn remove(&mut self, key: &K) -> Option<V> {
+    /// Never allocates.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find_slot(key)?;
+        let bucket = self.slots[index].as_mut().unwrap();
+        bucket.meta.access();
+        Some(bucket.value_mut())
+    }
+
+    pub fn get_cloned<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.get(key).cloned()
+    }
+
+    pub fn get_copied<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Copy,
+    {
+        self.get(key).copied()
+    }
+
+    /// Never allocates.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find_slot(key).is_some()
+    }
+
+    /// Looks up each key lazily, yielding `None` for keys that are missing.
+    pub fn get_all<'a, Q, I>(&'a self, keys: I) -> impl Iterator<Item = Option<&'a V>> + 'a
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'a,
+        I: IntoIterator<Item = &'a Q>,
+        I::IntoIter: 'a,
+    {
+        keys.into_iter().map(move |key| self.get(key))
+    }
+
+    /// Never allocates, apart from the checks run under the `shadow-model`
+    /// feature.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
         if let Some(index) = self.find_slot(key) {
-            let (_, value) = self.slots[index].take().unwrap();
-            self.size -= 1;
-            Some(value)
+            let bucket = self.remove_at(index);
+            self.shadow.verify(self);
+            Some(bucket.value)
         } else {
             None
         }
     }
+
+    /// Keeps only the entries for which `keep` returns `true`.
+    ///
+    /// `keep` sees every entry before any is removed, so if it panics the
+    /// table still holds all of its entries.
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, &mut V) -> bool) {
+        let kept: Vec<bool> = self
+            .slots
+            .iter_mut()
+            .map(|slot| {
+                slot.as_mut().is_none_or(|bucket| {
+                    let (key, value) = bucket.key_value_mut();
+                    keep(key, value)
+                })
+            })
+            .collect();
+        if kept.iter().all(|&kept| kept) {
+            return;
+        }
+        self.retain_slots(kept);
+    }
+
+    /// Drops the entries in slots whose flag in `kept` is `false`, placing
+    /// the rest again from their cached hashes. Runs no user code before
+    /// the removed entries are dropped.
+    fn retain_slots(&mut self, kept: Vec<bool>) {
+        let slot_count = self.slots.len();
+        let old_slots = mem::replace(&mut self.slots, empty_slots(slot_count));
+        self.size = 0;
+        self.generation += 1;
+
+        let mut removed = Vec::new();
+        for (bucket, kept) in old_slots.into_iter().zip(kept) {
+            let Some(bucket) = bucket else {
+                continue;
+            };
+
+            if kept {
+                self.place(bucket);
+            } else {
+                self.shadow.remove(&bucket);
+                removed.push(bucket);
+            }
+        }
+
+        self.shadow.verify(self);
+        drop(removed);
+    }
+
+    /// Exchanges the values stored under `a` and `b` in place. Returns
+    /// `false`, leaving the table unchanged, if either key is missing.
+    pub fn swap_values<Q>(&mut self, a: &Q, b: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (Some(first), Some(second)) = (self.find_slot(a), self.find_slot(b)) else {
+            return false;
+        };
+
+        if first != second {
+            let (low, high) = (first.min(second), first.max(second));
+            let (head, tail) = self.slots.split_at_mut(high);
+            let low_bucket = head[low].as_mut().unwrap();
+            let high_bucket = tail[0].as_mut().unwrap();
+
+            mem::swap(low_bucket.value_mut(), high_bucket.value_mut());
+        }
+
+        true
+    }
 }
 
-impl<K, V> HashTable<K, V>
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+    V: Default,
+{
+    /// Moves the value out of `key`'s entry, leaving `V::default()` behind.
+    /// The key stays in its slot, so the entry's position is unchanged.
+    pub fn take<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find_slot(key)?;
+        let bucket = self.slots[index].as_mut().unwrap();
+        Some(mem::take(bucket.value_mut()))
+    }
+}
+
+impl<K, V, S> HashTable<K, V, S>
 where
     K: Eq + Hash + Clone,
     V: Clone,
+    S: BuildHasher + Clone,
+{
+    /// Clones the table, returning an error instead of aborting when the
+    /// slot storage cannot be allocated. Allocations made by `K::clone` and
+    /// `V::clone` themselves are outside the table's control.
+    pub fn try_clone(&self) -> Result<Self, Error> {
+        let mut slots = Self::try_alloc_slots(self.slots.len())?;
+        slots.clone_from_slice(&self.slots);
+
+        Ok(Self {
+            slots,
+            size: self.size,
+            load_factor: self.load_factor,
+            probing: self.probing,
+            salt: self.salt,
+            generation: self.generation,
+            shadow: self.shadow.clone(),
+            probes: self.probes.duplicate(),
+            hash_builder: self.hash_builder.clone(),
+        })
+    }
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
 {
-    fn hash(&self, key: &K) -> usize {
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        hasher.finish() as usize % self.slots.len()
+    fn with_slots(slot_count: usize, load_factor: f64, probing: Probing, hash_builder: S) -> Self {
+        let slots = empty_slots(slot_count);
+
+        Self {
+            slots,
+            size: 0,
+            load_factor,
+            probing,
+            salt: 0,
+            generation: 0,
+            shadow: Shadow::new(),
+            probes: ProbeMonitor::new(),
+            hash_builder,
+        }
     }
 
-    fn find_slot(&self, key: &K) -> Option<usize> {
-        let mut index = self.hash(key);
+    fn try_with_slots(
+        slot_count: usize,
+        load_factor: f64,
+        probing: Probing,
+        hash_builder: S,
+    ) -> Result<Self, Error> {
+        let slots = Self::try_alloc_slots(slot_count)?;
+
+        Ok(Self {
+            slots,
+            size: 0,
+            load_factor,
+            probing,
+            salt: 0,
+            generation: 0,
+            shadow: Shadow::new(),
+            probes: ProbeMonitor::new(),
+            hash_builder,
+        })
+    }
+
+    /// An empty table with `slot_count` slots and the same configuration
+    /// and hasher as `self`, so cached hashes can move across.
+    fn sibling<K2, V2>(&self, slot_count: usize) -> HashTable<K2, V2, S>
+    where
+        K2: Eq + Hash,
+        S: Clone,
+    {
+        let mut table = HashTable::with_slots(
+            slot_count,
+            self.load_factor,
+            self.probing,
+            self.hash_builder.clone(),
+        );
+        table.salt = self.salt;
+        table
+    }
+
+    fn try_alloc_slots(slot_count: usize) -> Result<Vec<Option<Bucket<K, V>>>, Error> {
+        Layout::array::<Option<Bucket<K, V>>>(slot_count).map_err(|_| Error::CapacityOverflow)?;
+
+        let mut slots = Vec::new();
+        slots
+            .try_reserve_exact(slot_count)
+            .map_err(|_| Error::AllocError)?;
+        slots.resize_with(slot_count, || None);
+
+        Ok(slots)
+    }
+
+    fn make_hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        self.hash_builder.hash_one(key)
+    }
+
+    fn index_for(&self, hash: u64) -> usize {
+        self.probing
+            .homes(salted(hash, self.salt), self.slots.len())
+            .0
+    }
+
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        self.index_for(self.make_hash(key))
+    }
+
+    fn find_slot<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find_slot_hashed(self.make_hash(key), |stored| stored.borrow() == key)
+    }
+
+    fn find_slot_hashed(&self, hash: u64, eq: impl FnMut(&K) -> bool) -> Option<usize> {
+        let (found, probes) = self.locate(hash, eq);
+        let key = found.map(|index| &self.slots[index].as_ref().unwrap().key);
+        self.probes.record(probes, hash, key);
+        found
+    }
+
+    /// Like `find_slot_hashed` without reporting to the probe monitor, for
+    /// internal consistency checks that should not show up in probe stats.
+    fn locate(&self, hash: u64, mut eq: impl FnMut(&K) -> bool) -> (Option<usize>, usize) {
+        let mut probes = 0;
+        let mut found = self.probe_chain(self.index_for(hash), hash, &mut eq, &mut probes);
+        if let (None, Some(home)) = (found, self.second_home(hash)) {
+            found = self.probe_chain(home, hash, &mut eq, &mut probes);
+        }
+        (found, probes)
+    }
+
+    /// Scans from `home` for a matching entry, adding the number of slots
+    /// examined to `probes`.
+    fn probe_chain(
+        &self,
+        home: usize,
+        hash: u64,
+        eq: &mut impl FnMut(&K) -> bool,
+        probes: &mut usize,
+    ) -> Option<usize> {
         let capacity = self.slots.len();
+        let mut index = home;
 
-        while let Some((ref stored_key, _)) = &self.slots[index] {
-            if stored_key == key {
+        while let Some(bucket) = &self.slots[index] {
+            *probes += 1;
+            if bucket.hash == hash && eq(&bucket.key) {
                 return Some(index);
             }
 
             index = (index + 1) % capacity;
 
-            if index == self.hash(key) {
+            if index == home {
                 return None;
             }
         }
         None
     }
 
+    fn second_home(&self, hash: u64) -> Option<usize> {
+        self.probing
+            .homes(salted(hash, self.salt), self.slots.len())
+            .1
+    }
+
+    fn distance_to_empty(&self, home: usize) -> usize {
+        let capacity = self.slots.len();
+        let mut distance = 0;
+
+        while self.slots[(home + distance) % capacity].is_some() {
+            distance += 1;
+        }
+        distance
+    }
+
+    fn needs_resize(&self) -> bool {
+        self.size as f64 >= self.slots.len() as f64 * self.load_factor
+    }
+
+    fn insert_hashed(&mut self, hash: u64, key: K, value: V) -> Option<V> {
+        if let Some(index) = self.find_slot_hashed(hash, |stored| *stored == key) {
+            let bucket = self.slots[index].as_mut().unwrap();
+            Some(mem::replace(bucket.value_mut(), value))
+        } else {
+            self.insert_new(hash, key, value);
+            None
+        }
+    }
+
+    /// Inserts a key known to be absent, growing first if needed. Returns
+    /// the slot it was placed in.
+    fn insert_new(&mut self, hash: u64, key: K, value: V) -> usize {
+        self.insert_bucket(Bucket::new(hash, key, value))
+    }
+
+    /// Like `insert_new` for an entry moved from elsewhere, keeping its
+    /// metadata.
+    fn insert_bucket(&mut self, bucket: Bucket<K, V>) -> usize {
+        if self.needs_resize() {
+            self.resize();
+        }
+
+        self.shadow.insert(&bucket);
+        self.place(bucket)
+    }
+
+    /// Empties slot `index` and shifts later entries of the same cluster
+    /// back into the gap, so that no probe chain is left with a hole in it.
+    fn remove_at(&mut self, index: usize) -> Bucket<K, V> {
+        let bucket = self.slots[index].take().unwrap();
+        self.size -= 1;
+        self.generation += 1;
+        self.shadow.remove(&bucket);
+        self.close_gap(index);
+        bucket
+    }
+
+    fn close_gap(&mut self, mut hole: usize) {
+        let capacity = self.slots.len();
+        let mut index = (hole + 1) % capacity;
+
+        while let Some(hash) = self.slots[index].as_ref().map(|bucket| bucket.hash) {
+            if !self.reachable_past(hole, index, hash) {
+                self.slots[hole] = self.slots[index].take();
+                hole = index;
+            }
+            index = (index + 1) % capacity;
+        }
+    }
+
+    /// Whether an entry at `index` can still be found with `hole` empty,
+    /// i.e. whether one of its homes lies after `hole` and no further than
+    /// `index`. Everything between `hole` and `index` is occupied.
+    fn reachable_past(&self, hole: usize, index: usize, hash: u64) -> bool {
+        let capacity = self.slots.len();
+        let distance = |from: usize, to: usize| (to + capacity - from) % capacity;
+        let limit = distance(hole, index);
+
+        let in_range = |home: usize| (1..=limit).contains(&distance(hole, home));
+        in_range(self.index_for(hash)) || self.second_home(hash).is_some_and(in_range)
+    }
+
+    fn place(&mut self, bucket: Bucket<K, V>) -> usize {
+        let hash = bucket.hash;
+        let mut index = self.index_for(hash);
+        let capacity = self.slots.len();
+
+        if let Some(second) = self.second_home(hash) {
+            if self.distance_to_empty(second) < self.distance_to_empty(index) {
+                index = second;
+            }
+        }
+
+        while self.slots[index].is_some() {
+            index = (index + 1) % capacity;
+        }
+
+        self.slots[index] = Some(bucket);
+        self.size += 1;
+        index
+    }
+
     fn resize(&mut self) {
-        let new_slots = vec![None; self.slots.len() * 2];
+        let new_slots = empty_slots(self.slots.len() * 2);
+        self.rehash_into(new_slots);
+    }
+
+    fn try_rehash(&mut self, slot_count: usize) -> Result<(), Error> {
+        let new_slots = Self::try_alloc_slots(slot_count)?;
+        self.rehash_into(new_slots);
+        Ok(())
+    }
+
+    /// Moves every entry into `new_slots` using the cached hashes, so no
+    /// user `Hash` or `Eq` code runs while entries are in flight.
+    fn rehash_into(&mut self, new_slots: Vec<Option<Bucket<K, V>>>) {
         let old_slots = mem::replace(&mut self.slots, new_slots);
         self.size = 0;
+        self.generation += 1;
 
-        for slot in old_slots.into_iter().flatten() {
-            self.insert(slot.0, slot.1);
+        for bucket in old_slots.into_iter().flatten() {
+            self.place(bucket);
         }
     }
 }
 
-impl<K, V> Default for HashTable<K, V>
+/// Restores a cleared slot vector to `.1` empty slots when dropped, which
+/// also happens while unwinding.
+struct Refill<'a, K, V>(&'a mut Vec<Option<Bucket<K, V>>>, usize);
+
+impl<K, V> Drop for Refill<'_, K, V> {
+    fn drop(&mut self) {
+        self.0.resize_with(self.1, || None);
+    }
+}
+
+/// Mixes `salt` into `hash` so that tables with different salts send the
+/// same keys to unrelated slots. A multiply alone would keep the low bits,
+/// and with them the collisions, so the high half is folded back in.
+fn salted(hash: u64, salt: u64) -> u64 {
+    if salt == 0 {
+        return hash;
+    }
+
+    let mixed = (hash ^ salt).wrapping_mul(0xff51_afd7_ed55_8ccd);
+    mixed ^ (mixed >> 32)
+}
+
+fn empty_slots<K, V>(slot_count: usize) -> Vec<Option<Bucket<K, V>>> {
+    let mut slots = Vec::with_capacity(slot_count);
+    slots.resize_with(slot_count, || None);
+    slots
+}
+
+fn slots_for(capacity: usize, load_factor: f64) -> usize {
+    let needed = (capacity as f64 / load_factor).ceil() as usize;
+    needed.max(INITIAL_CAPACITY)
+}
+
+impl<K, V, S> Default for HashTable<K, V, S>
 where
-    K: Eq + Hash + Clone,
-    V: Clone,
+    K: Eq + Hash,
+    S: BuildHasher + Default,
 {
     fn default() -> Self {
-        Self::new()
+        Self::with_hasher(S::default())
     }
 }
 
@@ -149,6 +891,107 @@ mod tests {
         assert_eq!(table.remove(&"four"), None);
     }
 
+    #[test]
+    fn test_insert_returns_displaced_value() {
+        let mut table = HashTable::new();
+        assert_eq!(table.insert("a".to_string(), 1), None);
+        assert_eq!(table.insert("a".to_string(), 2), Some(1));
+        assert_eq!(table.len(), 1);
+
+        assert_eq!(table.get_copied("a"), Some(2));
+        assert_eq!(table.get_copied("b"), None);
+        assert!(table.contains_key("a"));
+        assert!(!table.contains_key("b"));
+
+        *table.get_mut("a").unwrap() += 40;
+        assert_eq!(table.get("a"), Some(&42));
+        assert_eq!(table.get_mut("b"), None);
+    }
+
+    #[test]
+    fn test_insert_many() {
+        let mut table = HashTable::new();
+        table.insert(0, "old");
+        let pairs: Vec<(u32, &str)> = (0..1000).map(|n| (n % 600, "first")).collect();
+        table.insert_many(pairs);
+        let slot_count = table.slots.len();
+        assert_eq!(slot_count, slots_for(600, table.load_factor));
+        assert_eq!(table.len(), 600);
+        assert_eq!(table.get(&0), Some(&"first"));
+
+        table.insert_from_slice(&[(7, "a"), (7, "b"), (599, "c")]);
+        assert_eq!(table.slots.len(), slot_count);
+        assert_eq!(table.get(&7), Some(&"b"));
+        assert_eq!(table.get(&599), Some(&"c"));
+        assert_eq!(table.len(), 600);
+        table.check_integrity().unwrap();
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut table = HashTable::new();
+        for i in 0..100 {
+            table.insert(i, i);
+        }
+
+        table.retain(|key, value| {
+            *value *= 2;
+            key % 3 == 0
+        });
+        assert_eq!(table.len(), 34);
+        for i in 0..100 {
+            let expected = (i % 3 == 0).then_some(i * 2);
+            assert_eq!(table.get(&i).copied(), expected);
+        }
+        table.check_integrity().unwrap();
+
+        table.retain(|_, _| false);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_get_all() {
+        let mut table: HashTable<&str, i32> = HashTable::new();
+        table.insert("one", 1);
+        table.insert("two", 2);
+
+        let keys = ["two", "three", "one"];
+        let values: Vec<Option<&i32>> = table.get_all(&keys).collect();
+        assert_eq!(values, vec![Some(&2), None, Some(&1)]);
+    }
+
+    #[test]
+    fn test_take() {
+        let mut table: HashTable<&str, Vec<i32>> = HashTable::new();
+        table.insert("jobs", vec![1, 2, 3]);
+        let slot = table.find_slot(&"jobs");
+
+        assert_eq!(table.get_cloned("jobs"), Some(vec![1, 2, 3]));
+        assert_eq!(table.take(&"jobs"), Some(vec![1, 2, 3]));
+        assert_eq!(table.get(&"jobs"), Some(&Vec::new()));
+        assert_eq!(table.find_slot(&"jobs"), slot);
+        assert_eq!(table.size, 1);
+
+        assert_eq!(table.take(&"missing"), None);
+    }
+
+    #[test]
+    fn test_swap_values() {
+        let mut table: HashTable<&str, String> = HashTable::new();
+        table.insert("a", "alpha".to_string());
+        table.insert("b", "beta".to_string());
+
+        assert!(table.swap_values(&"a", &"b"));
+        assert_eq!(table.get(&"a").map(String::as_str), Some("beta"));
+        assert_eq!(table.get(&"b").map(String::as_str), Some("alpha"));
+
+        assert!(table.swap_values(&"a", &"a"));
+        assert_eq!(table.get(&"a").map(String::as_str), Some("beta"));
+
+        assert!(!table.swap_values(&"a", &"missing"));
+        assert_eq!(table.get(&"a").map(String::as_str), Some("beta"));
+    }
+
     #[test]
     fn test_default() {
         let table: HashTable<&str, i32> = HashTable::default();
@@ -185,4 +1028,344 @@ mod tests {
         // Ensure the capacity has increased to accommodate the elements
         assert!(table.slots.len() >= 96);
     }
+
+    #[test]
+    fn test_two_choice_probing() {
+        fn average_probe_distance(table: &HashTable<u32, u32>) -> f64 {
+            let total: usize = table.buckets().filter_map(|b| b.probe_distance).sum();
+            total as f64 / table.size as f64
+        }
+
+        let builder = HashTableBuilder::new().load_factor(0.9);
+        let mut linear: HashTable<u32, u32> = builder.build();
+        let mut two_choice: HashTable<u32, u32> = builder.probing(Probing::TwoChoice).build();
+
+        for i in 0..10_000 {
+            linear.insert(i, i);
+            two_choice.insert(i, i);
+        }
+
+        for i in 0..10_000 {
+            assert_eq!(two_choice.get(&i), Some(&i));
+        }
+        assert_eq!(two_choice.get(&10_000), None);
+        assert_eq!(two_choice.check_integrity(), Ok(()));
+        assert!(average_probe_distance(&two_choice) < average_probe_distance(&linear));
+    }
+
+    #[test]
+    fn test_try_insert_and_try_resize() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+
+        for i in 0..32 {
+            assert_eq!(table.try_insert(i, i), Ok(None));
+        }
+        table.try_resize(1000).unwrap();
+        assert!(table.slots.len() >= 2000);
+
+        table.try_resize(0).unwrap();
+        assert!(table.slots.len() >= 64);
+        for i in 0..32 {
+            assert_eq!(table.get(&i), Some(&i));
+        }
+
+        assert_eq!(table.try_resize(usize::MAX), Err(Error::CapacityOverflow));
+        assert_eq!(table.size, 32);
+        assert_eq!(table.get(&7), Some(&7));
+    }
+
+    #[test]
+    fn test_try_clone() {
+        let mut table: HashTable<String, i32> = HashTable::new();
+        table.insert("one".to_string(), 1);
+        table.insert("two".to_string(), 2);
+
+        let mut cloned = table.try_clone().unwrap();
+        cloned.insert("three".to_string(), 3);
+
+        assert_eq!(cloned.size, 3);
+        assert_eq!(cloned.get(&"one".to_string()), Some(&1));
+        assert_eq!(table.size, 2);
+        assert_eq!(table.get(&"three".to_string()), None);
+    }
+
+    /// A small xorshift generator, so the mixed insert/remove runs below are
+    /// reproducible without pulling in a property-testing crate.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    fn check_against_model(probing: Probing, seed: u64) {
+        let mut rng = Rng(seed);
+        let mut table = HashTableBuilder::new().probing(probing).build();
+        let mut model = std::collections::HashMap::new();
+
+        for step in 0..4000 {
+            let key = rng.next() % 200;
+            if rng.next().is_multiple_of(3) {
+                assert_eq!(table.remove(&key), model.remove(&key), "step {step}");
+            } else {
+                table.insert(key, step);
+                model.insert(key, step);
+            }
+
+            assert_eq!(table.size, model.len());
+            for key in 0..200 {
+                assert_eq!(table.get(&key), model.get(&key), "step {step}");
+            }
+        }
+        table.check_integrity().unwrap();
+    }
+
+    #[test]
+    fn test_mixed_inserts_and_removes_linear() {
+        for seed in [1, 0x5EED, 0xDEAD_BEEF] {
+            check_against_model(Probing::Linear, seed);
+        }
+    }
+
+    #[test]
+    fn test_mixed_inserts_and_removes_two_choice() {
+        for seed in [1, 0x5EED, 0xDEAD_BEEF] {
+            check_against_model(Probing::TwoChoice, seed);
+        }
+    }
+
+    #[test]
+    fn test_remove_keeps_probe_chain_intact() {
+        let mut table = HashTable::new();
+        let slot_count = table.slots.len();
+        let colliding: Vec<u64> = (0..).filter(|key| table.hash(key) == 3).take(4).collect();
+        let wrapping: Vec<u64> = (0..)
+            .filter(|key| table.hash(key) == slot_count - 1)
+            .take(3)
+            .collect();
+
+        for &key in colliding.iter().chain(&wrapping) {
+            table.insert(key, key);
+        }
+        table.remove(&colliding[0]);
+        table.remove(&wrapping[0]);
+
+        for &key in colliding[1..].iter().chain(&wrapping[1..]) {
+            assert_eq!(table.get(&key), Some(&key));
+        }
+        table.check_integrity().unwrap();
+    }
+
+    #[test]
+    fn test_borrowed_lookups() {
+        let mut table: HashTable<String, usize> = HashTable::new();
+        table.insert("apple".to_string(), 1);
+        table.insert("pear".to_string(), 2);
+
+        assert_eq!(table.get("apple"), Some(&1));
+        assert!(table.swap_values("apple", "pear"));
+        assert_eq!(table.remove("pear"), Some(1));
+        assert_eq!(table.get("pear"), None);
+    }
+
+    #[test]
+    fn test_keys_and_values_need_not_be_clone() {
+        #[derive(PartialEq, Eq, Hash, Debug)]
+        struct Handle(u32);
+        struct Resource(Vec<u8>);
+
+        let mut table = HashTable::new();
+        for n in 0..40 {
+            table.insert(Handle(n), Resource(vec![n as u8]));
+        }
+
+        assert_eq!(table.get(&Handle(39)).map(|r| r.0[0]), Some(39));
+        assert_eq!(table.remove(&Handle(0)).map(|r| r.0), Some(vec![0]));
+        assert_eq!(table.size, 39);
+    }
+
+    #[test]
+    fn test_custom_hasher() {
+        use std::hash::{BuildHasherDefault, Hasher};
+
+        #[derive(Default)]
+        struct Constant;
+
+        impl Hasher for Constant {
+            fn finish(&self) -> u64 {
+                7
+            }
+
+            fn write(&mut self, _bytes: &[u8]) {}
+        }
+
+        let mut table =
+            HashTable::with_capacity_and_hasher(50, BuildHasherDefault::<Constant>::default());
+        let slot_count = table.slots.len();
+        for n in 0..50 {
+            table.insert(n, n * 10);
+        }
+
+        assert_eq!(table.slots.len(), slot_count);
+        assert_eq!(table.hasher().hash_one(123), 7);
+        assert_eq!(table.get(&49), Some(&490));
+        assert_eq!(table.remove(&0), Some(0));
+        assert_eq!(table.get(&1), Some(&10));
+        table.check_integrity().unwrap();
+    }
+
+    #[test]
+    fn test_capacity_management() {
+        let mut table = HashTable::with_capacity(1000);
+        let slot_count = table.slots.len();
+        assert!(table.capacity() >= 1000);
+        for n in 0..1000 {
+            table.insert(n, n);
+        }
+        assert_eq!(table.slots.len(), slot_count);
+        assert_eq!(table.len(), 1000);
+
+        table.reserve(500);
+        let slot_count = table.slots.len();
+        assert!(table.capacity() >= 1500);
+        for n in 1000..1500 {
+            table.insert(n, n);
+        }
+        assert_eq!(table.slots.len(), slot_count);
+
+        for n in 10..1500 {
+            table.remove(&n);
+        }
+        table.shrink_to_fit();
+        assert_eq!(table.slots.len(), slots_for(10, DEFAULT_LOAD_FACTOR));
+        assert_eq!(table.get(&9), Some(&9));
+
+        table.clear();
+        assert!(table.is_empty());
+        assert_eq!(table.get(&9), None);
+        table.insert(1, 1);
+        assert_eq!(table.len(), 1);
+    }
+
+    /// A key whose `Hash` and `Eq` panic once armed.
+    #[derive(Debug)]
+    struct Touchy {
+        id: u32,
+        armed: bool,
+    }
+
+    impl Touchy {
+        fn new(id: u32) -> Self {
+            Touchy { id, armed: false }
+        }
+    }
+
+    impl Hash for Touchy {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            assert!(!self.armed, "hash panicked");
+            self.id.hash(state);
+        }
+    }
+
+    impl PartialEq for Touchy {
+        fn eq(&self, other: &Self) -> bool {
+            assert!(!self.armed && !other.armed, "eq panicked");
+            self.id == other.id
+        }
+    }
+
+    impl Eq for Touchy {}
+
+    #[test]
+    fn test_panicking_keys_leave_table_intact() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut table = HashTable::new();
+        for id in 0..16 {
+            table.insert(Touchy::new(id), id);
+        }
+        let slot_count = table.slots.len();
+
+        // Sixteen entries sit right at the resize threshold, so a successful
+        // insert would have grown the table.
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            table.insert(
+                Touchy {
+                    id: 99,
+                    armed: true,
+                },
+                99,
+            );
+        }));
+        assert!(result.is_err());
+        assert_eq!(table.len(), 16);
+        assert_eq!(table.slots.len(), slot_count);
+        table.check_integrity().unwrap();
+
+        let hash = table.make_hash(&Touchy::new(5));
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let armed = Touchy { id: 5, armed: true };
+            table.find_slot_hashed(hash, |stored| *stored == armed)
+        }));
+        assert!(result.is_err());
+
+        table.insert(Touchy::new(99), 99);
+        assert_eq!(table.len(), 17);
+        assert_eq!(table.get(&Touchy::new(5)), Some(&5));
+        table.check_integrity().unwrap();
+    }
+
+    #[test]
+    fn test_clear_survives_panicking_drop() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        use std::cell::Cell;
+
+        struct Bomb<'a>(bool, &'a Cell<usize>);
+
+        impl Drop for Bomb<'_> {
+            fn drop(&mut self) {
+                self.1.set(self.1.get() + 1);
+                if mem::take(&mut self.0) {
+                    panic!("drop panicked");
+                }
+            }
+        }
+
+        let drops = Cell::new(0);
+        let mut table = HashTable::new();
+        for n in 0..20 {
+            table.insert(n, Bomb(n == 7, &drops));
+        }
+        let slot_count = table.slots.len();
+
+        let result = catch_unwind(AssertUnwindSafe(|| table.clear()));
+        assert!(result.is_err());
+        assert_eq!(drops.get(), 20);
+        assert!(table.is_empty());
+        assert_eq!(table.slots.len(), slot_count);
+        assert!(table.slots.iter().all(Option::is_none));
+
+        table.insert(1, Bomb(false, &drops));
+        assert_eq!(table.len(), 1);
+        table.check_integrity().unwrap();
+    }
+
+    #[cfg(feature = "entry-metadata")]
+    #[test]
+    fn test_retain_updates_metadata() {
+        let mut table: HashTable<u32, u32> = (0..10).map(|n| (n, n)).collect();
+        let before = table.entry_metadata(&3).unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        table.retain(|&key, value| {
+            *value += 1;
+            key != 4
+        });
+        assert!(table.entry_metadata(&3).unwrap().last_modified > before.last_modified);
+    }
 }
@@ -0,0 +1,146 @@
+//! Per-entry timestamps and access counts enabled by the `entry-metadata`
+//! feature.
+//!
+//! With the feature on, every entry records when it was inserted, when its
+//! value was last replaced or mutably borrowed, and how many times it was
+//! looked up through `get` or `get_mut`. Counts are atomics so that `get`
+//! can bump them through `&self` and tables stay `Sync`. Iterators never
+//! count as accesses, but mutable ones mark each entry they yield as
+//! modified. With the feature off, `Meta` is zero-sized.
+
+#[cfg(feature = "entry-metadata")]
+use std::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash},
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+#[cfg(feature = "entry-metadata")]
+use crate::HashTable;
+
+/// A snapshot of one entry's metadata.
+#[cfg(feature = "entry-metadata")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryMetadata {
+    pub created_at: SystemTime,
+    pub last_modified: SystemTime,
+    pub access_count: u64,
+}
+
+#[cfg(feature = "entry-metadata")]
+pub(crate) struct Meta {
+    created_at: SystemTime,
+    last_modified: SystemTime,
+    access_count: AtomicU64,
+}
+
+#[cfg(not(feature = "entry-metadata"))]
+#[derive(Clone)]
+pub(crate) struct Meta;
+
+#[cfg(feature = "entry-metadata")]
+impl Meta {
+    pub(crate) fn new() -> Self {
+        let now = SystemTime::now();
+        Self {
+            created_at: now,
+            last_modified: now,
+            access_count: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn access(&self) {
+        self.access_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn touch(&mut self) {
+        self.last_modified = SystemTime::now();
+    }
+
+    fn snapshot(&self) -> EntryMetadata {
+        EntryMetadata {
+            created_at: self.created_at,
+            last_modified: self.last_modified,
+            access_count: self.access_count.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(feature = "entry-metadata")]
+impl Clone for Meta {
+    fn clone(&self) -> Self {
+        Self {
+            created_at: self.created_at,
+            last_modified: self.last_modified,
+            access_count: AtomicU64::new(self.access_count.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[cfg(not(feature = "entry-metadata"))]
+impl Meta {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+
+    #[inline(always)]
+    pub(crate) fn access(&self) {}
+
+    #[inline(always)]
+    pub(crate) fn touch(&mut self) {}
+}
+
+#[cfg(feature = "entry-metadata")]
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// The metadata of `key`'s entry. Does not count as an access.
+    pub fn entry_metadata<Q>(&self, key: &Q) -> Option<EntryMetadata>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find_slot(key)?;
+        Some(self.slots[index].as_ref().unwrap().meta.snapshot())
+    }
+}
+
+#[cfg(all(test, feature = "entry-metadata"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_metadata() {
+        let mut table: HashTable<String, u32> = HashTable::new();
+        table.insert("audit".to_string(), 1);
+        let created = table.entry_metadata("audit").unwrap();
+        assert_eq!(created.created_at, created.last_modified);
+        assert_eq!(created.access_count, 0);
+
+        table.get("audit");
+        table.get("audit");
+        assert_eq!(table.entry_metadata("audit").unwrap().access_count, 2);
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        *table.get_mut("audit").unwrap() += 1;
+        let modified = table.entry_metadata("audit").unwrap();
+        assert_eq!(modified.created_at, created.created_at);
+        assert!(modified.last_modified > created.last_modified);
+        assert_eq!(modified.access_count, 3);
+        assert_eq!(table.entry_metadata("missing"), None);
+
+        for n in 0..100 {
+            table.insert(n.to_string(), n);
+        }
+        assert_eq!(table.entry_metadata("audit"), Some(modified));
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        table.values_mut().for_each(|value| *value += 1);
+        let iterated = table.entry_metadata("audit").unwrap();
+        assert!(iterated.last_modified > modified.last_modified);
+        assert_eq!(iterated.access_count, modified.access_count);
+    }
+}
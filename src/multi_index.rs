@@ -0,0 +1,381 @@
+use std::{any::Any, borrow::Borrow, fmt, hash::Hash, marker::PhantomData};
+
+use crate::HashTable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultiIndexError {
+    /// Another entry already has this key in the named index.
+    DuplicateKey { index: &'static str },
+}
+
+impl fmt::Display for MultiIndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiIndexError::DuplicateKey { index } => {
+                write!(f, "key already present in index {index:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MultiIndexError {}
+
+type Extractor<V, I> = Box<dyn Fn(&V) -> I>;
+
+/// Names one index of a [`MultiIndexTable`] together with its key type,
+/// so lookups through it are checked at compile time. Obtained from
+/// [`MultiIndexTable::index`].
+pub struct IndexHandle<I> {
+    position: usize,
+    marker: PhantomData<fn() -> I>,
+}
+
+impl<I> Clone for IndexHandle<I> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I> Copy for IndexHandle<I> {}
+
+impl<I> fmt::Debug for IndexHandle<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexHandle")
+            .field("position", &self.position)
+            .finish()
+    }
+}
+
+struct Index<V, I> {
+    name: &'static str,
+    extract: Extractor<V, I>,
+    ids: HashTable<I, u64>,
+}
+
+/// An [`Index`] with its key type erased, so indexes keyed by different
+/// types can sit side by side.
+trait ErasedIndex<V> {
+    fn name(&self) -> &'static str;
+
+    /// The concrete `Index`, for recovering its key type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Whether `value`'s key is owned by an entry other than `owner`.
+    fn conflicts(&self, value: &V, owner: Option<u64>) -> bool;
+
+    fn link(&mut self, value: &V, id: u64);
+
+    fn unlink(&mut self, value: &V);
+}
+
+impl<V, I> ErasedIndex<V> for Index<V, I>
+where
+    V: 'static,
+    I: Eq + Hash + 'static,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn conflicts(&self, value: &V, owner: Option<u64>) -> bool {
+        let key = (self.extract)(value);
+        self.ids.get(&key).is_some_and(|&id| Some(id) != owner)
+    }
+
+    fn link(&mut self, value: &V, id: u64) {
+        self.ids.insert((self.extract)(value), id);
+    }
+
+    fn unlink(&mut self, value: &V) {
+        self.ids.remove(&(self.extract)(value));
+    }
+}
+
+/// A table whose entries can be looked up through several unique keys, each
+/// derived from the value by a declared extractor (e.g. by ID, by email,
+/// by handle). Each index has its own key type, and lookups go through an
+/// [`IndexHandle`] from [`index`](Self::index) that carries it.
+///
+/// Extractors run again whenever an entry is linked into or unlinked from
+/// the indexes, so they must return the same key for the same value.
+/// Every mutation checks all indexes before touching any of them, so an
+/// insert or replace that would collide in one index leaves every index
+/// unchanged.
+pub struct MultiIndexTable<V> {
+    entries: HashTable<u64, V>,
+    indexes: Vec<Box<dyn ErasedIndex<V>>>,
+    next_id: u64,
+}
+
+impl<V> MultiIndexTable<V> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashTable::new(),
+            indexes: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Declares a unique index named `name`.
+    ///
+    /// Panics if the table already holds entries or if an index with the
+    /// same name exists.
+    pub fn with_index<I>(mut self, name: &'static str, extract: impl Fn(&V) -> I + 'static) -> Self
+    where
+        V: 'static,
+        I: Eq + Hash + 'static,
+    {
+        assert!(self.is_empty(), "indexes must be declared before inserting");
+        assert!(
+            self.position(name).is_none(),
+            "index {name:?} declared twice"
+        );
+
+        self.indexes.push(Box::new(Index {
+            name,
+            extract: Box::new(extract),
+            ids: HashTable::new(),
+        }));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn insert(&mut self, value: V) -> Result<(), MultiIndexError> {
+        self.check(&value, None)?;
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.link(&value, id);
+        self.entries.insert(id, value);
+        Ok(())
+    }
+
+    /// A handle to the index named `name`, or `None` if there is no such
+    /// index or it is not keyed by `I`.
+    pub fn index<I>(&self, name: &str) -> Option<IndexHandle<I>>
+    where
+        V: 'static,
+        I: Eq + Hash + 'static,
+    {
+        let position = self.position(name)?;
+        self.indexes[position]
+            .as_any()
+            .is::<Index<V, I>>()
+            .then_some(IndexHandle {
+                position,
+                marker: PhantomData,
+            })
+    }
+
+    /// Looks up the entry whose key in `index` is `key`.
+    pub fn get_by<I, Q>(&self, index: IndexHandle<I>, key: &Q) -> Option<&V>
+    where
+        V: 'static,
+        I: Eq + Hash + Borrow<Q> + 'static,
+        Q: Hash + Eq + ?Sized,
+    {
+        let id = self.id_by(index, key)?;
+        self.entries.get(&id)
+    }
+
+    pub fn remove_by<I, Q>(&mut self, index: IndexHandle<I>, key: &Q) -> Option<V>
+    where
+        V: 'static,
+        I: Eq + Hash + Borrow<Q> + 'static,
+        Q: Hash + Eq + ?Sized,
+    {
+        let id = self.id_by(index, key)?;
+        let value = self.entries.remove(&id)?;
+        self.unlink(&value);
+        Some(value)
+    }
+
+    /// Replaces the entry found through `index`/`key` with `value`,
+    /// re-keying it in every index, and returns the old value. Returns
+    /// `Ok(None)` if no entry matches.
+    pub fn replace_by<I, Q>(
+        &mut self,
+        index: IndexHandle<I>,
+        key: &Q,
+        value: V,
+    ) -> Result<Option<V>, MultiIndexError>
+    where
+        V: 'static,
+        I: Eq + Hash + Borrow<Q> + 'static,
+        Q: Hash + Eq + ?Sized,
+    {
+        let Some(id) = self.id_by(index, key) else {
+            return Ok(None);
+        };
+
+        self.check(&value, Some(id))?;
+        let old = self.entries.remove(&id).unwrap();
+        self.unlink(&old);
+        self.link(&value, id);
+        self.entries.insert(id, value);
+        Ok(Some(old))
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.indexes.iter().position(|index| index.name() == name)
+    }
+
+    /// The id under `key` in `index`. A handle taken from another table
+    /// whose index at that position has a different key type finds nothing.
+    fn id_by<I, Q>(&self, index: IndexHandle<I>, key: &Q) -> Option<u64>
+    where
+        V: 'static,
+        I: Eq + Hash + Borrow<Q> + 'static,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self
+            .indexes
+            .get(index.position)?
+            .as_any()
+            .downcast_ref::<Index<V, I>>()?;
+        index.ids.get(key).copied()
+    }
+
+    /// Fails if any of `value`'s keys is already owned by an entry other
+    /// than `owner`.
+    fn check(&self, value: &V, owner: Option<u64>) -> Result<(), MultiIndexError> {
+        match self
+            .indexes
+            .iter()
+            .find(|index| index.conflicts(value, owner))
+        {
+            Some(index) => Err(MultiIndexError::DuplicateKey {
+                index: index.name(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn link(&mut self, value: &V, id: u64) {
+        for index in &mut self.indexes {
+            index.link(value, id);
+        }
+    }
+
+    fn unlink(&mut self, value: &V) {
+        for index in &mut self.indexes {
+            index.unlink(value);
+        }
+    }
+}
+
+impl<V> Default for MultiIndexTable<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct User {
+        id: u32,
+        email: &'static str,
+        handle: &'static str,
+    }
+
+    fn users() -> MultiIndexTable<User> {
+        MultiIndexTable::new()
+            .with_index("id", |user: &User| user.id)
+            .with_index("email", |user: &User| user.email.to_string())
+            .with_index("handle", |user: &User| user.handle)
+    }
+
+    struct Handles {
+        id: IndexHandle<u32>,
+        email: IndexHandle<String>,
+        handle: IndexHandle<&'static str>,
+    }
+
+    fn handles(table: &MultiIndexTable<User>) -> Handles {
+        Handles {
+            id: table.index("id").unwrap(),
+            email: table.index("email").unwrap(),
+            handle: table.index("handle").unwrap(),
+        }
+    }
+
+    fn user(id: u32, email: &'static str, handle: &'static str) -> User {
+        User { id, email, handle }
+    }
+
+    #[test]
+    fn test_lookup_by_every_index() {
+        let mut table = users();
+        let index = handles(&table);
+        table.insert(user(1, "ann@example.com", "ann")).unwrap();
+        table.insert(user(2, "bob@example.com", "bob")).unwrap();
+
+        let ann = user(1, "ann@example.com", "ann");
+        assert_eq!(table.get_by(index.id, &1_u32), Some(&ann));
+        assert_eq!(table.get_by(index.email, "ann@example.com"), Some(&ann));
+        assert_eq!(table.get_by(index.handle, &"ann"), Some(&ann));
+
+        assert_eq!(
+            table.remove_by(index.handle, &"bob"),
+            Some(user(2, "bob@example.com", "bob"))
+        );
+        assert_eq!(table.get_by(index.email, "bob@example.com"), None);
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_handles_check_name_and_key_type() {
+        let mut table = users();
+        table.insert(user(1, "ann@example.com", "ann")).unwrap();
+
+        assert!(table.index::<u32>("nope").is_none());
+        assert!(table.index::<&str>("id").is_none());
+
+        let other: MultiIndexTable<User> =
+            MultiIndexTable::new().with_index("id", |user: &User| user.handle);
+        let foreign: IndexHandle<&'static str> = other.index("id").unwrap();
+        assert_eq!(table.get_by(foreign, &"ann"), None);
+    }
+
+    #[test]
+    fn test_conflicts_leave_indexes_untouched() {
+        let mut table = users();
+        let index = handles(&table);
+        table.insert(user(1, "ann@example.com", "ann")).unwrap();
+        table.insert(user(2, "bob@example.com", "bob")).unwrap();
+
+        assert_eq!(
+            table.insert(user(3, "carol@example.com", "ann")),
+            Err(MultiIndexError::DuplicateKey { index: "handle" })
+        );
+        assert_eq!(table.get_by(index.id, &3_u32), None);
+        assert_eq!(table.len(), 2);
+
+        assert_eq!(
+            table.replace_by(index.id, &2_u32, user(2, "ann@example.com", "bobby")),
+            Err(MultiIndexError::DuplicateKey { index: "email" })
+        );
+        assert_eq!(table.get_by(index.handle, &"bob").map(|u| u.id), Some(2));
+
+        let old = table
+            .replace_by(index.id, &2_u32, user(2, "bob@example.com", "bobby"))
+            .unwrap();
+        assert_eq!(old.map(|u| u.handle), Some("bob"));
+        assert_eq!(table.get_by(index.handle, &"bob"), None);
+        assert_eq!(table.get_by(index.handle, &"bobby").map(|u| u.id), Some(2));
+    }
+}
@@ -0,0 +1,189 @@
+use std::{borrow::Borrow, hash::Hash};
+
+use crate::HashTable;
+
+/// A table keyed by `(namespace, key)` pairs, with per-namespace counts and
+/// bulk operations.
+///
+/// Each namespace keeps its entries in a table of its own, dropped once the
+/// namespace empties, so lookups take borrowed forms of either part of the
+/// key without building a tuple. [`namespace_len`] is O(1);
+/// [`iter_namespace`] and [`clear_namespace`] only visit the namespace's own
+/// slots, so their cost follows the size of that namespace, not the whole
+/// table.
+///
+/// [`namespace_len`]: Self::namespace_len
+/// [`iter_namespace`]: Self::iter_namespace
+/// [`clear_namespace`]: Self::clear_namespace
+pub struct NamespacedTable<N, K, V> {
+    namespaces: HashTable<N, HashTable<K, V>>,
+    size: usize,
+}
+
+impl<N, K, V> NamespacedTable<N, K, V>
+where
+    N: Eq + Hash,
+    K: Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            namespaces: HashTable::new(),
+            size: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of entries in `namespace`.
+    pub fn namespace_len<QN>(&self, namespace: &QN) -> usize
+    where
+        N: Borrow<QN>,
+        QN: Hash + Eq + ?Sized,
+    {
+        self.namespaces.get(namespace).map_or(0, HashTable::len)
+    }
+
+    /// Inserts `value` under `key` in `namespace` and returns the value it
+    /// displaced, if any.
+    pub fn insert(&mut self, namespace: N, key: K, value: V) -> Option<V> {
+        let displaced = self
+            .namespaces
+            .entry(namespace)
+            .or_default()
+            .insert(key, value);
+        if displaced.is_none() {
+            self.size += 1;
+        }
+        displaced
+    }
+
+    pub fn get<QN, QK>(&self, namespace: &QN, key: &QK) -> Option<&V>
+    where
+        N: Borrow<QN>,
+        K: Borrow<QK>,
+        QN: Hash + Eq + ?Sized,
+        QK: Hash + Eq + ?Sized,
+    {
+        self.namespaces.get(namespace)?.get(key)
+    }
+
+    pub fn get_mut<QN, QK>(&mut self, namespace: &QN, key: &QK) -> Option<&mut V>
+    where
+        N: Borrow<QN>,
+        K: Borrow<QK>,
+        QN: Hash + Eq + ?Sized,
+        QK: Hash + Eq + ?Sized,
+    {
+        self.namespaces.get_mut(namespace)?.get_mut(key)
+    }
+
+    pub fn remove<QN, QK>(&mut self, namespace: &QN, key: &QK) -> Option<V>
+    where
+        N: Borrow<QN>,
+        K: Borrow<QK>,
+        QN: Hash + Eq + ?Sized,
+        QK: Hash + Eq + ?Sized,
+    {
+        let entries = self.namespaces.get_mut(namespace)?;
+        let value = entries.remove(key)?;
+        if entries.is_empty() {
+            self.namespaces.remove(namespace);
+        }
+        self.size -= 1;
+        Some(value)
+    }
+
+    /// The keys and values in `namespace`, in no particular order.
+    pub fn iter_namespace<QN>(&self, namespace: &QN) -> impl Iterator<Item = (&K, &V)>
+    where
+        N: Borrow<QN>,
+        QN: Hash + Eq + ?Sized,
+    {
+        self.namespaces.get(namespace).into_iter().flatten()
+    }
+
+    /// Removes every entry in `namespace`, returning how many there were.
+    pub fn clear_namespace<QN>(&mut self, namespace: &QN) -> usize
+    where
+        N: Borrow<QN>,
+        QN: Hash + Eq + ?Sized,
+    {
+        let count = self
+            .namespaces
+            .remove(namespace)
+            .map_or(0, |entries| entries.len());
+        self.size -= count;
+        count
+    }
+}
+
+impl<N, K, V> Default for NamespacedTable<N, K, V>
+where
+    N: Eq + Hash,
+    K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespaces() {
+        let mut table: NamespacedTable<String, String, u32> = NamespacedTable::new();
+        for n in 0..50 {
+            table.insert("tenant-a".to_string(), format!("key-{n}"), n);
+        }
+        table.insert("tenant-b".to_string(), "key-1".to_string(), 100);
+        assert_eq!(
+            table.insert("tenant-b".to_string(), "key-1".to_string(), 101),
+            Some(100)
+        );
+
+        assert_eq!(table.len(), 51);
+        assert_eq!(table.namespace_len("tenant-a"), 50);
+        assert_eq!(table.namespace_len("tenant-b"), 1);
+        assert_eq!(table.get("tenant-a", "key-1"), Some(&1));
+        assert_eq!(table.get("tenant-b", "key-1"), Some(&101));
+        assert_eq!(table.get("tenant-c", "key-1"), None);
+
+        *table.get_mut("tenant-b", "key-1").unwrap() += 1;
+        let b: Vec<_> = table.iter_namespace("tenant-b").collect();
+        assert_eq!(b, [(&"key-1".to_string(), &102)]);
+        assert_eq!(table.iter_namespace("tenant-a").count(), 50);
+
+        assert_eq!(table.remove("tenant-b", "key-1"), Some(102));
+        assert_eq!(table.namespace_len("tenant-b"), 0);
+        assert_eq!(table.iter_namespace("tenant-b").count(), 0);
+
+        assert_eq!(table.clear_namespace("tenant-a"), 50);
+        assert!(table.is_empty());
+        assert!(table.namespaces.is_empty());
+    }
+
+    #[test]
+    fn test_clear_namespace_keeps_other_namespaces() {
+        let mut table: NamespacedTable<u32, u32, u32> = NamespacedTable::new();
+        for n in 0..1000 {
+            table.insert(n % 10, n, n);
+        }
+        assert_eq!(table.clear_namespace(&3), 100);
+        assert_eq!(table.clear_namespace(&3), 0);
+        assert_eq!(table.len(), 900);
+        assert_eq!(table.get(&4, &4), Some(&4));
+        assert_eq!(table.get(&3, &3), None);
+
+        let mut keys: Vec<_> = table.iter_namespace(&7).map(|(key, _)| *key).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, (0..100).map(|n| n * 10 + 7).collect::<Vec<_>>());
+    }
+}
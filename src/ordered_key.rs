@@ -0,0 +1,153 @@
+use std::{
+    borrow::Borrow,
+    collections::BTreeSet,
+    hash::Hash,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::HashTable;
+
+/// A [`HashTable`] with a secondary index ordering its keys, for range and
+/// prefix queries over namespaced keys such as `"user:42"`.
+///
+/// Point lookups go through the hash table alone; inserts and removals of
+/// new keys also update the ordered index, which holds a clone of each key.
+pub struct OrderedKeyTable<K, V>
+where
+    K: Eq + Hash + Ord + Clone,
+{
+    entries: HashTable<K, V>,
+    index: BTreeSet<K>,
+}
+
+impl<K, V> OrderedKeyTable<K, V>
+where
+    K: Eq + Hash + Ord + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            entries: HashTable::new(),
+            index: BTreeSet::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts `value` under `key` and returns the value it displaced, if
+    /// any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(existing) = self.entries.get_mut(&key) {
+            return Some(std::mem::replace(existing, value));
+        }
+        self.index.insert(key.clone());
+        self.entries.insert(key, value)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.get(key)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.get_mut(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.contains_key(key)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        let value = self.entries.remove(key)?;
+        self.index.remove(key);
+        Some(value)
+    }
+
+    /// Entries in ascending key order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> {
+        self.range::<K, _>(..)
+    }
+
+    /// Entries whose keys fall in `range`, in ascending key order.
+    pub fn range<Q, R>(&self, range: R) -> impl DoubleEndedIterator<Item = (&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        self.index
+            .range(range)
+            .map(|key| (key, self.entries.get::<K>(key).unwrap()))
+    }
+
+    /// Entries whose keys start with `prefix`, in ascending key order.
+    pub fn iter_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a K, &'a V)>
+    where
+        K: Borrow<str>,
+    {
+        self.range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |(key, _)| (*key).borrow().starts_with(prefix))
+    }
+}
+
+impl<K, V> Default for OrderedKeyTable<K, V>
+where
+    K: Eq + Hash + Ord + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_and_range_queries() {
+        let mut table = OrderedKeyTable::new();
+        for key in ["user:2", "order:7", "user:10", "user:1", "users", "usera"] {
+            table.insert(key.to_string(), key.len());
+        }
+        assert_eq!(table.insert("user:1".to_string(), 0), Some(6));
+
+        let users: Vec<&str> = table
+            .iter_prefix("user:")
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(users, ["user:1", "user:10", "user:2"]);
+        assert_eq!(table.get("user:1"), Some(&0));
+
+        let range: Vec<&str> = table
+            .range::<str, _>((Bound::Included("user:10"), Bound::Excluded("user:3")))
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(range, ["user:10", "user:2"]);
+
+        assert_eq!(table.remove("user:10"), Some(7));
+        assert_eq!(table.iter_prefix("user:").count(), 2);
+        assert_eq!(table.iter().next().unwrap().0, "order:7");
+        assert_eq!(table.len(), 5);
+        assert_eq!(table.iter_prefix("nothing").count(), 0);
+    }
+}
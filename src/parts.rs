@@ -0,0 +1,222 @@
+//! Disjoint mutable views over a table's slots, for mutating one table from
+//! several threads without locking.
+
+use std::{
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    ops::Range,
+};
+
+use crate::{salted, Bucket, HashTable, Probing};
+
+/// Mutable access to the entries stored in one contiguous range of slots.
+///
+/// Parts are slot ranges, not hash ranges: an entry's home slot is its hash
+/// modulo the slot count, so a part holds the entries whose homes fall in
+/// its range plus any that probing displaced into it from earlier slots.
+/// Entries can be read and their values changed, but not inserted or
+/// removed, since that could move entries across parts.
+pub struct TablePartMut<'a, K, V, S = RandomState> {
+    slots: &'a mut [Option<Bucket<K, V>>],
+    offset: usize,
+    slot_count: usize,
+    probing: Probing,
+    salt: u64,
+    hash_builder: &'a S,
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Splits the slots into at most `parts` non-overlapping views of
+    /// roughly equal size, in slot order. Every entry lands in exactly one
+    /// view, chosen by the slot it is stored in rather than by its hash, so
+    /// which view holds a key is only known once it has been inserted. The
+    /// views can be sent to different threads when `K`, `V` and `S` allow
+    /// it.
+    ///
+    /// Panics if `parts` is zero.
+    pub fn split_at_mut_by_slot(&mut self, parts: usize) -> Vec<TablePartMut<'_, K, V, S>> {
+        assert!(parts > 0, "cannot split a table into zero parts");
+
+        let slot_count = self.slots.len();
+        let chunk = slot_count.div_ceil(parts);
+        let (probing, salt) = (self.probing, self.salt);
+        let hash_builder = &self.hash_builder;
+
+        self.slots
+            .chunks_mut(chunk)
+            .enumerate()
+            .map(|(index, slots)| TablePartMut {
+                slots,
+                offset: index * chunk,
+                slot_count,
+                probing,
+                salt,
+                hash_builder,
+            })
+            .collect()
+    }
+}
+
+impl<K, V, S> TablePartMut<'_, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// The table slots this part covers.
+    pub fn slot_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.slots.len()
+    }
+
+    /// Number of entries in this part. Counts the slots, so it is linear in
+    /// the size of the part.
+    pub fn len(&self) -> usize {
+        self.slots.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.slots.iter_mut().flatten().map(Bucket::key_value_mut)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    /// The value for `key` if its entry is stored in this part, `None` if
+    /// it is stored in another part or absent.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_builder.hash_one(key);
+        let (primary, second) = self.probing.homes(salted(hash, self.salt), self.slot_count);
+
+        let index = [Some(primary), second]
+            .into_iter()
+            .flatten()
+            .filter_map(|home| home.checked_sub(self.offset))
+            .chain([0])
+            .find_map(|start| self.probe_local(start, hash, key))?;
+        Some(self.slots[index].as_mut().unwrap().value_mut())
+    }
+
+    /// Follows the run of occupied slots from local index `start` to the end
+    /// of this part, returning the local index of `key`'s entry.
+    ///
+    /// Probe chains have no gaps, so an entry whose chain starts before
+    /// this part, or wraps around the end of the table into it, lies in the
+    /// run starting at local index 0.
+    fn probe_local<Q>(&self, start: usize, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let chain = self.slots.get(start..)?;
+
+        for (step, slot) in chain.iter().enumerate() {
+            let bucket = slot.as_ref()?;
+            if bucket.hash == hash && bucket.key.borrow() == key {
+                return Some(start + step);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn test_parts_cover_the_table() {
+        let mut table: HashTable<u32, u32> = (0..500).map(|n| (n, n)).collect();
+        let slot_count = table.slots.len();
+
+        let parts = table.split_at_mut_by_slot(3);
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].slot_range().start, 0);
+        assert_eq!(parts[2].slot_range().end, slot_count);
+        assert_eq!(parts.iter().map(TablePartMut::len).sum::<usize>(), 500);
+    }
+
+    #[test]
+    fn test_parallel_mutation() {
+        let mut table: HashTable<u32, u32> = (0..1000).map(|n| (n, n)).collect();
+
+        thread::scope(|scope| {
+            for mut part in table.split_at_mut_by_slot(4) {
+                scope.spawn(move || {
+                    for value in part.values_mut() {
+                        *value *= 2;
+                    }
+                });
+            }
+        });
+
+        for n in 0..1000 {
+            assert_eq!(table.get(&n), Some(&(n * 2)));
+        }
+    }
+
+    #[test]
+    fn test_get_mut_finds_every_key_in_its_part() {
+        for probing in [Probing::Linear, Probing::TwoChoice] {
+            let mut table: HashTable<u32, u32> = crate::HashTableBuilder::new()
+                .probing(probing)
+                .load_factor(0.9)
+                .build();
+            table.extend((0..200).map(|n| (n, 0)));
+            let parts = table.slots.len() / 3;
+
+            for mut part in table.split_at_mut_by_slot(parts) {
+                let stored: Vec<u32> = part.iter_mut().map(|(&key, _)| key).collect();
+                for key in 0..200 {
+                    match part.get_mut(&key) {
+                        Some(value) => {
+                            assert!(stored.contains(&key));
+                            *value += 1;
+                        }
+                        None => assert!(!stored.contains(&key)),
+                    }
+                }
+            }
+
+            assert!(table.values().all(|&hits| hits == 1));
+        }
+    }
+
+    #[cfg(feature = "entry-metadata")]
+    #[test]
+    fn test_part_mutation_updates_metadata() {
+        let mut table: HashTable<u32, u32> = (0..20).map(|n| (n, n)).collect();
+        let before = table.entry_metadata(&7).unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        for mut part in table.split_at_mut_by_slot(2) {
+            if let Some(value) = part.get_mut(&7) {
+                *value += 1;
+            }
+        }
+        let after_get_mut = table.entry_metadata(&7).unwrap();
+        assert!(after_get_mut.last_modified > before.last_modified);
+        let untouched = table.entry_metadata(&8).unwrap();
+        assert_eq!(untouched.last_modified, untouched.created_at);
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        for mut part in table.split_at_mut_by_slot(2) {
+            part.values_mut().for_each(|value| *value += 1);
+        }
+        assert!(table.entry_metadata(&7).unwrap().last_modified > after_get_mut.last_modified);
+    }
+}
@@ -0,0 +1,206 @@
+//! Probe-count instrumentation enabled by the `probe-check` feature.
+//!
+//! With the feature on, every table counts the slots each lookup examines
+//! and can panic or log when a single lookup exceeds a limit, naming the
+//! key involved. Counters are atomics so tables stay `Sync`. With the
+//! feature off, `ProbeMonitor` is zero-sized and records nothing.
+
+#[cfg(not(feature = "probe-check"))]
+use std::marker::PhantomData;
+
+#[cfg(feature = "probe-check")]
+use std::{
+    fmt::Debug,
+    hash::{BuildHasher, Hash},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+#[cfg(feature = "probe-check")]
+use crate::HashTable;
+
+/// What to do when a lookup probes more slots than the configured limit.
+#[cfg(feature = "probe-check")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeAction {
+    Panic,
+    /// Print a report to stderr and carry on.
+    Log,
+}
+
+/// Probe counts recorded since the table was created.
+#[cfg(feature = "probe-check")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProbeStats {
+    pub lookups: u64,
+    pub total_probes: u64,
+    pub longest: usize,
+}
+
+#[cfg(feature = "probe-check")]
+pub(crate) struct ProbeMonitor<K> {
+    lookups: AtomicU64,
+    total_probes: AtomicU64,
+    longest: AtomicUsize,
+    limit: Option<Limit<K>>,
+}
+
+#[cfg(feature = "probe-check")]
+struct Limit<K> {
+    probes: usize,
+    action: ProbeAction,
+    describe: fn(&K) -> String,
+}
+
+#[cfg(not(feature = "probe-check"))]
+pub(crate) struct ProbeMonitor<K>(PhantomData<fn(&K)>);
+
+#[cfg(feature = "probe-check")]
+impl<K> ProbeMonitor<K> {
+    pub(crate) fn new() -> Self {
+        Self {
+            lookups: AtomicU64::new(0),
+            total_probes: AtomicU64::new(0),
+            longest: AtomicUsize::new(0),
+            limit: None,
+        }
+    }
+
+    /// Records a lookup for `hash` that examined `probes` slots and found
+    /// `found`, if anything.
+    pub(crate) fn record(&self, probes: usize, hash: u64, found: Option<&K>) {
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+        self.total_probes
+            .fetch_add(probes as u64, Ordering::Relaxed);
+        self.longest.fetch_max(probes, Ordering::Relaxed);
+
+        let Some(limit) = &self.limit else {
+            return;
+        };
+        if probes <= limit.probes {
+            return;
+        }
+
+        let key = match found {
+            Some(key) => format!("key {}", (limit.describe)(key)),
+            None => format!("missing key with hash {hash:#018x}"),
+        };
+        let report = format!(
+            "lookup of {key} probed {probes} slots (limit {})",
+            limit.probes
+        );
+        match limit.action {
+            ProbeAction::Panic => panic!("{report}"),
+            ProbeAction::Log => eprintln!("hash_table: {report}"),
+        }
+    }
+
+    pub(crate) fn duplicate(&self) -> Self {
+        Self {
+            lookups: AtomicU64::new(self.lookups.load(Ordering::Relaxed)),
+            total_probes: AtomicU64::new(self.total_probes.load(Ordering::Relaxed)),
+            longest: AtomicUsize::new(self.longest.load(Ordering::Relaxed)),
+            limit: self.limit.as_ref().map(|limit| Limit {
+                probes: limit.probes,
+                action: limit.action,
+                describe: limit.describe,
+            }),
+        }
+    }
+}
+
+#[cfg(not(feature = "probe-check"))]
+impl<K> ProbeMonitor<K> {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    #[inline(always)]
+    pub(crate) fn record(&self, _probes: usize, _hash: u64, _found: Option<&K>) {}
+
+    pub(crate) fn duplicate(&self) -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "probe-check")]
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn probe_stats(&self) -> ProbeStats {
+        let monitor = &self.probes;
+        ProbeStats {
+            lookups: monitor.lookups.load(Ordering::Relaxed),
+            total_probes: monitor.total_probes.load(Ordering::Relaxed),
+            longest: monitor.longest.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reports every later lookup that examines more than `probes` slots,
+    /// naming its key if it was found.
+    pub fn set_probe_limit(&mut self, probes: usize, action: ProbeAction)
+    where
+        K: Debug,
+    {
+        self.probes.limit = Some(Limit {
+            probes,
+            action,
+            describe: |key| format!("{key:?}"),
+        });
+    }
+}
+
+#[cfg(all(test, feature = "probe-check"))]
+mod tests {
+    use super::*;
+
+    use std::{
+        hash::{BuildHasherDefault, Hasher},
+        panic::{catch_unwind, AssertUnwindSafe},
+    };
+
+    #[derive(Default)]
+    struct Constant;
+
+    impl Hasher for Constant {
+        fn finish(&self) -> u64 {
+            7
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    #[test]
+    fn test_probe_stats() {
+        let mut table: HashTable<u32, u32> = HashTable::new();
+        for n in 0..100 {
+            table.insert(n, n);
+        }
+        let before = table.probe_stats();
+        assert_eq!(before.lookups, 100);
+
+        table.get(&5);
+        let after = table.probe_stats();
+        assert_eq!(after.lookups, 101);
+        assert!(after.total_probes > before.total_probes);
+        assert!(after.longest >= 1);
+    }
+
+    #[test]
+    fn test_probe_limit_names_offending_key() {
+        let mut table = HashTable::with_hasher(BuildHasherDefault::<Constant>::default());
+        for n in 0..20 {
+            table.insert(format!("key-{n}"), n);
+        }
+        table.set_probe_limit(8, ProbeAction::Panic);
+
+        assert_eq!(table.get("key-3"), Some(&3));
+        let panic = catch_unwind(AssertUnwindSafe(|| table.get("key-15"))).unwrap_err();
+        let report = panic.downcast_ref::<String>().unwrap();
+        assert!(report.contains("\"key-15\""), "{report}");
+
+        table.set_probe_limit(8, ProbeAction::Log);
+        assert_eq!(table.get("key-15"), Some(&15));
+    }
+}
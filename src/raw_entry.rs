@@ -0,0 +1,254 @@
+//! Lookups driven by a caller-supplied hash and match closure, for keys that
+//! are expensive or impossible to build just to probe the table.
+
+use std::{
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    mem,
+};
+
+use crate::HashTable;
+
+/// Shared-access lookups from a precomputed hash. Created by
+/// [`HashTable::raw_entry`].
+pub struct RawEntryBuilder<'a, K, V, S = RandomState> {
+    table: &'a HashTable<K, V, S>,
+}
+
+/// Exclusive-access lookups from a precomputed hash. Created by
+/// [`HashTable::raw_entry_mut`].
+pub struct RawEntryBuilderMut<'a, K, V, S = RandomState> {
+    table: &'a mut HashTable<K, V, S>,
+}
+
+pub enum RawEntryMut<'a, K, V, S = RandomState> {
+    Occupied(RawOccupiedEntryMut<'a, K, V, S>),
+    Vacant(RawVacantEntryMut<'a, K, V, S>),
+}
+
+pub struct RawOccupiedEntryMut<'a, K, V, S = RandomState> {
+    table: &'a mut HashTable<K, V, S>,
+    index: usize,
+}
+
+pub struct RawVacantEntryMut<'a, K, V, S = RandomState> {
+    table: &'a mut HashTable<K, V, S>,
+    hash: u64,
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Starts a lookup that takes the hash and key match from the caller.
+    /// Hashes must come from this table's [`hasher`](Self::hasher), e.g.
+    /// `table.hasher().hash_one(("ns", "name"))` for a `(String, String)`
+    /// key.
+    pub fn raw_entry(&self) -> RawEntryBuilder<'_, K, V, S> {
+        RawEntryBuilder { table: self }
+    }
+
+    /// Like [`raw_entry`](Self::raw_entry), but the result can insert,
+    /// update or remove.
+    pub fn raw_entry_mut(&mut self) -> RawEntryBuilderMut<'_, K, V, S> {
+        RawEntryBuilderMut { table: self }
+    }
+}
+
+impl<'a, K, V, S> RawEntryBuilder<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Finds the entry with hash `hash` for which `is_match` returns `true`.
+    pub fn from_hash(self, hash: u64, is_match: impl FnMut(&K) -> bool) -> Option<(&'a K, &'a V)> {
+        let index = self.table.find_slot_hashed(hash, is_match)?;
+        let bucket = self.table.slots[index].as_ref().unwrap();
+        Some((&bucket.key, &bucket.value))
+    }
+
+    pub fn from_key<Q>(self, key: &Q) -> Option<(&'a K, &'a V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.table.make_hash(key);
+        self.from_hash(hash, |stored| stored.borrow() == key)
+    }
+}
+
+impl<'a, K, V, S> RawEntryBuilderMut<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Finds the entry with hash `hash` for which `is_match` returns `true`,
+    /// or a vacant entry that will insert under `hash`.
+    pub fn from_hash(
+        self,
+        hash: u64,
+        is_match: impl FnMut(&K) -> bool,
+    ) -> RawEntryMut<'a, K, V, S> {
+        match self.table.find_slot_hashed(hash, is_match) {
+            Some(index) => RawEntryMut::Occupied(RawOccupiedEntryMut {
+                table: self.table,
+                index,
+            }),
+            None => RawEntryMut::Vacant(RawVacantEntryMut {
+                table: self.table,
+                hash,
+            }),
+        }
+    }
+
+    pub fn from_key<Q>(self, key: &Q) -> RawEntryMut<'a, K, V, S>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.table.make_hash(key);
+        self.from_hash(hash, |stored| stored.borrow() == key)
+    }
+}
+
+impl<'a, K, V, S> RawEntryMut<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Returns the existing entry, or inserts the pair built by `default`.
+    pub fn or_insert_with(self, default: impl FnOnce() -> (K, V)) -> (&'a mut K, &'a mut V) {
+        match self {
+            RawEntryMut::Occupied(entry) => entry.into_key_value(),
+            RawEntryMut::Vacant(entry) => {
+                let (key, value) = default();
+                entry.insert(key, value)
+            }
+        }
+    }
+}
+
+impl<'a, K, V, S> RawOccupiedEntryMut<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        &self.table.slots[self.index].as_ref().unwrap().key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.table.slots[self.index].as_ref().unwrap().value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.table.slots[self.index].as_mut().unwrap().value_mut()
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.table.slots[self.index].as_mut().unwrap().value_mut()
+    }
+
+    /// Mutable access to the key. Changing it so that it no longer hashes
+    /// or compares the same makes the entry unreachable.
+    pub fn into_key_value(self) -> (&'a mut K, &'a mut V) {
+        let bucket = self.table.slots[self.index].as_mut().unwrap();
+        bucket.meta.touch();
+        (&mut bucket.key, &mut bucket.value)
+    }
+
+    /// Replaces the value, returning the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        let bucket = self.table.remove_at(self.index);
+        self.table.shadow.verify(self.table);
+        (bucket.key, bucket.value)
+    }
+}
+
+impl<'a, K, V, S> RawVacantEntryMut<'a, K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Inserts the pair under the hash the lookup was made with, without
+    /// hashing `key` again. The two must agree; debug builds check that
+    /// they do.
+    pub fn insert(self, key: K, value: V) -> (&'a mut K, &'a mut V) {
+        debug_assert_eq!(
+            self.hash,
+            self.table.make_hash(&key),
+            "raw entry hash does not match the inserted key"
+        );
+
+        let index = self.table.insert_new(self.hash, key, value);
+        self.table.shadow.verify(self.table);
+        let bucket = self.table.slots[index].as_mut().unwrap();
+        (&mut bucket.key, &mut bucket.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn qualified(table: &HashTable<(String, String), u32>, ns: &str, name: &str) -> Option<u32> {
+        let hash = table.hasher().hash_one((ns, name));
+        table
+            .raw_entry()
+            .from_hash(hash, |(stored_ns, stored_name)| {
+                stored_ns == ns && stored_name == name
+            })
+            .map(|(_, value)| *value)
+    }
+
+    #[test]
+    fn test_composite_key_lookup_without_allocating() {
+        let mut table = HashTable::new();
+        table.insert(("net".to_string(), "port".to_string()), 80);
+        table.insert(("net".to_string(), "host".to_string()), 1);
+
+        assert_eq!(qualified(&table, "net", "port"), Some(80));
+        assert_eq!(qualified(&table, "net", "mtu"), None);
+        let key = ("net".to_string(), "host".to_string());
+        assert_eq!(table.raw_entry().from_key(&key), Some((&key, &1)));
+    }
+
+    #[test]
+    fn test_raw_entry_mut() {
+        let mut table: HashTable<(String, String), u32> = HashTable::new();
+
+        for name in ["a", "b", "a", "a"] {
+            let hash = table.hasher().hash_one(("ns", name));
+            let (_, count) = table
+                .raw_entry_mut()
+                .from_hash(hash, |(ns, stored)| ns == "ns" && stored == name)
+                .or_insert_with(|| (("ns".to_string(), name.to_string()), 0));
+            *count += 1;
+        }
+        assert_eq!(table.get(&("ns".to_string(), "a".to_string())), Some(&3));
+
+        let key = ("ns".to_string(), "b".to_string());
+        match table.raw_entry_mut().from_key(&key) {
+            RawEntryMut::Occupied(mut entry) => {
+                assert_eq!(entry.insert(10), 1);
+                assert_eq!(entry.remove_entry(), (key.clone(), 10));
+            }
+            RawEntryMut::Vacant(_) => panic!("expected an occupied entry"),
+        }
+        assert_eq!(table.len(), 1);
+        assert!(matches!(
+            table.raw_entry_mut().from_key(&key),
+            RawEntryMut::Vacant(_)
+        ));
+    }
+}
@@ -0,0 +1,182 @@
+//! Taking a table apart into plain components and putting it back together.
+
+use std::hash::{BuildHasher, Hash};
+
+use crate::{Bucket, HashTable, IntegrityError, ProbeMonitor, Probing, Shadow};
+
+/// A table's storage and configuration with nothing hidden.
+///
+/// `slots` is the slot array in order: `None` for an empty slot, otherwise
+/// the key's cached hash, the key and the value. The entry count is not
+/// stored separately; it is the number of occupied slots.
+pub struct RawParts<K, V, S> {
+    pub slots: Vec<Option<(u64, K, V)>>,
+    pub load_factor: f64,
+    pub probing: Probing,
+    pub salt: u64,
+    pub hash_builder: S,
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Decomposes the table without rehashing or moving any entry between
+    /// slots.
+    pub fn into_raw_parts(self) -> RawParts<K, V, S> {
+        let slots = self
+            .slots
+            .into_iter()
+            .map(|slot| slot.map(|bucket| (bucket.hash, bucket.key, bucket.value)))
+            .collect();
+
+        RawParts {
+            slots,
+            load_factor: self.load_factor,
+            probing: self.probing,
+            salt: self.salt,
+            hash_builder: self.hash_builder,
+        }
+    }
+
+    /// Rebuilds a table from parts produced by
+    /// [`into_raw_parts`](Self::into_raw_parts), possibly after they were
+    /// stored or moved elsewhere.
+    ///
+    /// Every cached hash is checked against `hash_builder` and every entry
+    /// must be reachable from its home slot, so this is linear in the number
+    /// of slots and hashes every key. Empty `slots` and a load factor
+    /// outside `0.0 < load_factor < 1.0` are rejected as well.
+    pub fn from_raw_parts(parts: RawParts<K, V, S>) -> Result<Self, IntegrityError> {
+        let RawParts {
+            slots,
+            load_factor,
+            probing,
+            salt,
+            hash_builder,
+        } = parts;
+        if slots.is_empty() {
+            return Err(IntegrityError::NoSlots);
+        }
+        if !(load_factor > 0.0 && load_factor < 1.0) {
+            return Err(IntegrityError::InvalidLoadFactor);
+        }
+
+        if let Some(slot) = slots.iter().position(|slot| {
+            slot.as_ref()
+                .is_some_and(|(hash, key, _)| *hash != hash_builder.hash_one(key))
+        }) {
+            return Err(IntegrityError::HashMismatch { slot });
+        }
+
+        let slots: Vec<_> = slots
+            .into_iter()
+            .map(|slot| slot.map(|(hash, key, value)| Bucket::new(hash, key, value)))
+            .collect();
+        let mut table = Self {
+            size: slots.iter().flatten().count(),
+            slots,
+            load_factor,
+            probing,
+            salt,
+            generation: 0,
+            shadow: Shadow::new(),
+            probes: ProbeMonitor::new(),
+            hash_builder,
+        };
+        table.check_integrity()?;
+        table.shadow = Shadow::mirror(&table);
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::hash_map::RandomState;
+
+    use crate::{Error, HashTableBuilder};
+
+    #[test]
+    fn test_raw_parts_round_trip() {
+        let mut table: HashTable<u32, String> = HashTableBuilder::new()
+            .probing(Probing::TwoChoice)
+            .salt(7)
+            .build();
+        for n in 0..300 {
+            table.insert(n, n.to_string());
+        }
+        table.remove(&150);
+        let slot_count = table.slots.len();
+
+        let parts = table.into_raw_parts();
+        assert_eq!(parts.slots.len(), slot_count);
+        assert_eq!(parts.slots.iter().flatten().count(), 299);
+
+        let table = HashTable::from_raw_parts(parts).unwrap();
+        assert_eq!(table.len(), 299);
+        assert_eq!(table.salt(), 7);
+        assert_eq!(table.get(&42).map(String::as_str), Some("42"));
+        assert_eq!(table.get(&150), None);
+    }
+
+    #[test]
+    fn test_from_raw_parts_rejects_bad_parts() {
+        let table: HashTable<u32, u32> = (0..10).map(|n| (n, n)).collect();
+
+        let mut parts = table.into_raw_parts();
+        let slot = parts.slots.iter().position(Option::is_some).unwrap();
+        parts.slots[slot].as_mut().unwrap().0 ^= 1;
+        assert_eq!(
+            HashTable::from_raw_parts(parts).err(),
+            Some(IntegrityError::HashMismatch { slot })
+        );
+
+        let table: HashTable<u32, u32> = [(1, 1)].into_iter().collect();
+        let mut parts = table.into_raw_parts();
+        let home = parts.slots.iter().position(Option::is_some).unwrap();
+        let slot = (home + 1) % parts.slots.len();
+        parts.slots.swap(home, slot);
+        assert_eq!(
+            HashTable::from_raw_parts(parts).err(),
+            Some(IntegrityError::UnreachableKey { slot })
+        );
+
+        let table: HashTable<u32, u32> = HashTable::new();
+        let mut parts = table.into_raw_parts();
+        parts.load_factor = 1.0;
+        assert_eq!(
+            HashTable::from_raw_parts(parts).err(),
+            Some(IntegrityError::InvalidLoadFactor)
+        );
+
+        let table: HashTable<u32, u32> = HashTable::new();
+        let mut parts = table.into_raw_parts();
+        parts.slots.clear();
+        assert_eq!(
+            HashTable::from_raw_parts(parts).err(),
+            Some(IntegrityError::NoSlots)
+        );
+    }
+
+    #[test]
+    fn test_integrity_errors_convert_to_error() {
+        fn rebuild(parts: RawParts<u32, u32, RandomState>) -> Result<HashTable<u32, u32>, Error> {
+            let table = HashTable::from_raw_parts(parts)?;
+            table.check_integrity()?;
+            Ok(table)
+        }
+
+        let table: HashTable<u32, u32> = (0..10).map(|n| (n, n)).collect();
+        assert_eq!(rebuild(table.into_raw_parts()).unwrap().len(), 10);
+
+        let table: HashTable<u32, u32> = HashTable::new();
+        let mut parts = table.into_raw_parts();
+        parts.slots.clear();
+        let error = rebuild(parts).err().unwrap();
+        assert_eq!(error, Error::Corruption(IntegrityError::NoSlots));
+        assert!(std::error::Error::source(&error).is_some());
+    }
+}
@@ -0,0 +1,129 @@
+//! Whole-table scans split into chunks, so long scans can pause in between.
+
+use std::{
+    future::Future,
+    hash::{BuildHasher, Hash},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::HashTable;
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Calls `f` with successive chunks of up to `chunk_size` entries, in
+    /// slot order, until every entry has been passed once.
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn for_each_chunked(&self, chunk_size: usize, mut f: impl FnMut(&[(&K, &V)])) {
+        assert!(chunk_size > 0, "chunk size must be at least 1");
+
+        let mut chunk = Vec::with_capacity(chunk_size.min(self.size));
+        for entry in self.iter() {
+            chunk.push(entry);
+            if chunk.len() == chunk_size {
+                f(&chunk);
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            f(&chunk);
+        }
+    }
+
+    /// Like [`for_each_chunked`](Self::for_each_chunked), but yields to the
+    /// executor after every chunk so that a long scan inside an async task
+    /// does not hold up other tasks on the same thread. Works with any
+    /// executor.
+    pub async fn for_each_chunked_async(&self, chunk_size: usize, mut f: impl FnMut(&[(&K, &V)])) {
+        assert!(chunk_size > 0, "chunk size must be at least 1");
+
+        let mut chunk = Vec::with_capacity(chunk_size.min(self.size));
+        for entry in self.iter() {
+            chunk.push(entry);
+            if chunk.len() == chunk_size {
+                f(&chunk);
+                chunk.clear();
+                YieldNow(false).await;
+            }
+        }
+        if !chunk.is_empty() {
+            f(&chunk);
+        }
+    }
+}
+
+/// Returns `Pending` once, waking itself first, then `Ready`.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        pin::pin,
+        sync::Arc,
+        task::{Wake, Waker},
+    };
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Polls `future` to completion, returning its output and how many
+    /// times it yielded.
+    fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        let mut yields = 0;
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return (output, yields),
+                Poll::Pending => yields += 1,
+            }
+        }
+    }
+
+    #[test]
+    fn test_for_each_chunked() {
+        let table: HashTable<u32, u32> = (0..250).map(|n| (n, n)).collect();
+
+        let mut sizes = Vec::new();
+        let mut total = 0;
+        table.for_each_chunked(100, |chunk| {
+            sizes.push(chunk.len());
+            total += chunk.iter().map(|(_, value)| **value).sum::<u32>();
+        });
+        assert_eq!(sizes, [100, 100, 50]);
+        assert_eq!(total, (0..250).sum());
+    }
+
+    #[test]
+    fn test_for_each_chunked_async_yields_between_chunks() {
+        let table: HashTable<u32, u32> = (0..250).map(|n| (n, n)).collect();
+
+        let mut seen = 0;
+        let ((), yields) = block_on(table.for_each_chunked_async(100, |chunk| seen += chunk.len()));
+        assert_eq!(seen, 250);
+        assert_eq!(yields, 2);
+    }
+}
@@ -0,0 +1,94 @@
+//! Keys that must not leak through lookup timing.
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{Hash, Hasher},
+    hint,
+};
+
+use crate::HashTable;
+
+/// A table keyed by secrets, hashed with a randomly keyed SipHash.
+///
+/// Probing only ever branches on keyed hashes and on [`SecretKey`]'s
+/// constant-time equality, so neither the probe count nor the comparison
+/// time of a lookup depends on stored key bytes in a way an attacker
+/// without the hash key can exploit.
+pub type SecretTable<V> = HashTable<SecretKey, V, RandomState>;
+
+/// Secret bytes (an API token, say) with constant-time equality and a
+/// redacted `Debug`.
+///
+/// There is deliberately no `Borrow<[u8]>` impl: lookups by plain slice
+/// would compare with `[u8]`'s short-circuiting equality.
+#[derive(Clone)]
+pub struct SecretKey(Box<[u8]>);
+
+impl SecretKey {
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        SecretKey(bytes.into().into_boxed_slice())
+    }
+
+    pub fn expose(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl PartialEq for SecretKey {
+    /// Examines every byte of the longer key whatever the contents, so the
+    /// time taken depends only on the lengths.
+    fn eq(&self, other: &Self) -> bool {
+        let (a, b) = (&self.0, &other.0);
+        let mut diff = (a.len() ^ b.len()) as u64;
+        for index in 0..a.len().max(b.len()) {
+            let (x, y) = (a.get(index), b.get(index));
+            diff |= u64::from(x.unwrap_or(&0) ^ y.unwrap_or(&0));
+        }
+        hint::black_box(diff) == 0
+    }
+}
+
+impl Eq for SecretKey {}
+
+impl Hash for SecretKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey({} bytes)", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_equality() {
+        assert_eq!(SecretKey::new("token"), SecretKey::new(*b"token"));
+        assert_ne!(SecretKey::new("token"), SecretKey::new("tokeN"));
+        assert_ne!(SecretKey::new("token"), SecretKey::new("token\0"));
+        assert_ne!(SecretKey::new(""), SecretKey::new("\0"));
+        assert_eq!(SecretKey::new(""), SecretKey::new(""));
+    }
+
+    #[test]
+    fn test_secret_table() {
+        let mut tokens: SecretTable<&str> = HashTable::new();
+        for n in 0..50 {
+            tokens.insert(SecretKey::new(format!("token-{n}")), "user");
+        }
+        tokens.insert(SecretKey::new("admin-token"), "admin");
+
+        assert_eq!(tokens.get(&SecretKey::new("admin-token")), Some(&"admin"));
+        assert_eq!(tokens.get(&SecretKey::new("admin-tokem")), None);
+        assert_eq!(
+            format!("{:?}", SecretKey::new("admin-token")),
+            "SecretKey(11 bytes)"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Differential-testing mirror enabled by the `shadow-model` feature.
+//!
+//! With the feature on, every entry carries an [`EntryId`] that is unique
+//! for the life of the process, and every table keeps a
+//! `std::collections::HashMap` from the ids of its entries to their hashes.
+//! After each mutation the table is checked against the model in full:
+//! both must hold exactly the same entries, each with the hash it was
+//! recorded with, and every entry must be reachable. An entry that a buggy
+//! path drops, duplicates or swaps for another shows up as a divergence.
+//! Tracking ids rather than copies of the entries keeps the model free of
+//! any `Clone` requirement on keys and values.
+//!
+//! Checking is O(capacity) per mutation, so tests run much slower with the
+//! feature on. With the feature off, `Shadow` and `EntryId` are zero-sized
+//! and all of their methods compile to nothing.
+
+use std::hash::{BuildHasher, Hash};
+
+use crate::{Bucket, HashTable};
+
+/// Identifies one entry from its creation until it is dropped. Moving an
+/// entry between slots or tables keeps its id; replacing its value in place
+/// does too.
+#[cfg(feature = "shadow-model")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct EntryId(u64);
+
+#[cfg(not(feature = "shadow-model"))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EntryId;
+
+#[cfg(feature = "shadow-model")]
+impl EntryId {
+    pub(crate) fn new() -> Self {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+#[cfg(not(feature = "shadow-model"))]
+impl EntryId {
+    #[inline(always)]
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "shadow-model")]
+#[derive(Clone)]
+pub(crate) struct Shadow(std::collections::HashMap<EntryId, u64>);
+
+#[cfg(not(feature = "shadow-model"))]
+#[derive(Clone)]
+pub(crate) struct Shadow;
+
+#[cfg(feature = "shadow-model")]
+impl Shadow {
+    pub(crate) fn new() -> Self {
+        Self(std::collections::HashMap::new())
+    }
+
+    pub(crate) fn mirror<K, V, S>(table: &HashTable<K, V, S>) -> Self {
+        let mut shadow = Self::new();
+        for bucket in table.slots.iter().flatten() {
+            shadow.insert(bucket);
+        }
+        shadow
+    }
+
+    /// Records a new entry.
+    pub(crate) fn insert<K, V>(&mut self, bucket: &Bucket<K, V>) {
+        let previous = self.0.insert(bucket.id, bucket.hash);
+        assert!(
+            previous.is_none(),
+            "shadow model diverged: entry inserted twice"
+        );
+    }
+
+    pub(crate) fn remove<K, V>(&mut self, bucket: &Bucket<K, V>) {
+        assert_eq!(
+            self.0.remove(&bucket.id),
+            Some(bucket.hash),
+            "shadow model diverged: removed entry was not in model"
+        );
+    }
+
+    pub(crate) fn verify<K: Eq + Hash, V, S: BuildHasher>(&self, table: &HashTable<K, V, S>) {
+        assert_eq!(
+            table.size,
+            self.0.len(),
+            "shadow model diverged: table size differs from model"
+        );
+
+        let mut seen = std::collections::HashSet::new();
+        for (index, slot) in table.slots.iter().enumerate() {
+            let Some(bucket) = slot else {
+                continue;
+            };
+            assert!(
+                self.0.get(&bucket.id) == Some(&bucket.hash) && seen.insert(bucket.id),
+                "shadow model diverged: table holds different entries than model"
+            );
+            assert_eq!(
+                table.locate(bucket.hash, |key| *key == bucket.key).0,
+                Some(index),
+                "shadow model diverged: entry in table is unreachable or duplicated"
+            );
+        }
+        assert_eq!(
+            seen.len(),
+            self.0.len(),
+            "shadow model diverged: table holds different entries than model"
+        );
+    }
+}
+
+#[cfg(not(feature = "shadow-model"))]
+impl Shadow {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+
+    #[inline(always)]
+    pub(crate) fn mirror<K, V, S>(_table: &HashTable<K, V, S>) -> Self {
+        Self
+    }
+
+    #[inline(always)]
+    pub(crate) fn insert<K, V>(&mut self, _bucket: &Bucket<K, V>) {}
+
+    #[inline(always)]
+    pub(crate) fn remove<K, V>(&mut self, _bucket: &Bucket<K, V>) {}
+
+    #[inline(always)]
+    pub(crate) fn verify<K: Eq + Hash, V, S: BuildHasher>(&self, _table: &HashTable<K, V, S>) {}
+}
+
+#[cfg(all(test, feature = "shadow-model"))]
+mod tests {
+    use crate::{Bucket, HashTable};
+
+    #[test]
+    fn test_shadow_tracks_mutations() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        for i in 0..100 {
+            table.insert(i, i);
+        }
+        table.insert(5, 50);
+        assert_eq!(table.shadow.0.len(), 100);
+        let index = table.find_slot(&5).unwrap();
+        let bucket = table.slots[index].as_ref().unwrap();
+        assert_eq!(table.shadow.0.get(&bucket.id), Some(&table.make_hash(&5)));
+
+        let cloned = table.try_clone().unwrap();
+        assert_eq!(cloned.shadow.0.len(), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "shadow model diverged")]
+    fn test_shadow_detects_divergence() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        table.insert(1, 1);
+        table.slots.iter_mut().for_each(|slot| *slot = None);
+        table.size = 0;
+        table.insert(2, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "unreachable or duplicated")]
+    fn test_shadow_detects_misplaced_entry() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        table.insert(1, 1);
+        let home = table.slots.iter().position(Option::is_some).unwrap();
+        let len = table.slots.len();
+        table.slots.swap(home, (home + len - 1) % len);
+        table.insert(2, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "holds different entries than model")]
+    fn test_shadow_detects_replaced_entry() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        table.insert(1, 1);
+        let index = table.find_slot(&1).unwrap();
+        let hash = table.make_hash(&1);
+        table.slots[index] = Some(Bucket::new(hash, 1, 100));
+        table.insert(2, 2);
+    }
+}
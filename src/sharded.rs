@@ -0,0 +1,270 @@
+//! A hash table split across independently locked shards.
+
+use std::{
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    ops::Deref,
+    sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    thread,
+};
+
+use crate::HashTable;
+
+/// A concurrent table that spreads keys over a fixed number of shards, each
+/// an ordinary [`HashTable`] behind its own `RwLock`.
+///
+/// Operations on keys in different shards never contend. The shard is
+/// picked from the upper half of the key's hash, so it stays independent of
+/// the slot the shard's table puts the key in.
+///
+/// A panic while a shard is locked poisons that lock; the shard is still
+/// used afterwards, as with [`SwappableTable`](crate::SwappableTable).
+pub struct ShardedHashTable<K, V, S = RandomState> {
+    shards: Box<[RwLock<HashTable<K, V, S>>]>,
+    hash_builder: S,
+}
+
+/// A read-locked reference to a value in a [`ShardedHashTable`]. The
+/// value's shard stays read-locked until the guard is dropped.
+pub struct ValueRef<'a, K, V, S> {
+    shard: RwLockReadGuard<'a, HashTable<K, V, S>>,
+    index: usize,
+}
+
+impl<K, V, S> Deref for ValueRef<'_, K, V, S> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.shard.slots[self.index].as_ref().unwrap().value
+    }
+}
+
+impl<K, V> ShardedHashTable<K, V, RandomState>
+where
+    K: Eq + Hash,
+{
+    /// A table with four shards per available CPU, rounded up to a power of
+    /// two.
+    pub fn new() -> Self {
+        let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_shards((cpus * 4).next_power_of_two())
+    }
+
+    pub fn with_shards(shard_count: usize) -> Self {
+        Self::with_shards_and_hasher(shard_count, RandomState::new())
+    }
+}
+
+impl<K, V, S> ShardedHashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Panics if `shard_count` is zero.
+    pub fn with_shards_and_hasher(shard_count: usize, hash_builder: S) -> Self {
+        assert!(shard_count > 0, "sharded table needs at least one shard");
+
+        let shards = (0..shard_count)
+            .map(|_| RwLock::new(HashTable::with_hasher(hash_builder.clone())))
+            .collect();
+        Self {
+            shards,
+            hash_builder,
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Total number of entries. Shards are counted one at a time, so under
+    /// concurrent writes the result is only a snapshot.
+    pub fn len(&self) -> usize {
+        (0..self.shards.len())
+            .map(|shard| self.read(shard).len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let shard = self.shard_for(&key);
+        self.write(shard).insert(key, value)
+    }
+
+    /// Read-locks `key`'s shard and returns a guard for its value.
+    pub fn get<Q>(&self, key: &Q) -> Option<ValueRef<'_, K, V, S>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let shard = self.read(self.shard_for(key));
+        let index = shard.find_slot(key)?;
+        shard.slots[index].as_ref().unwrap().meta.access();
+        Some(ValueRef { shard, index })
+    }
+
+    /// Clones `key`'s value out, holding the shard lock only for the copy.
+    pub fn get_cloned<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.read(self.shard_for(key)).get_cloned(key)
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.write(self.shard_for(key)).remove(key)
+    }
+
+    /// Runs `f` on `key`'s value under the shard's write lock.
+    pub fn update<Q, R>(&self, key: &Q, f: impl FnOnce(&mut V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut shard = self.write(self.shard_for(key));
+        let index = shard.find_slot(key)?;
+        Some(f(shard.slots[index].as_mut().unwrap().value_mut()))
+    }
+
+    fn shard_for<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        let hash = self.hash_builder.hash_one(key);
+        (hash >> 32) as usize % self.shards.len()
+    }
+
+    fn read(&self, shard: usize) -> RwLockReadGuard<'_, HashTable<K, V, S>> {
+        self.shards[shard]
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self, shard: usize) -> RwLockWriteGuard<'_, HashTable<K, V, S>> {
+        self.shards[shard]
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K, V> Default for ShardedHashTable<K, V, RandomState>
+where
+    K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_is_send_and_sync() {
+        assert_send_sync::<ShardedHashTable<String, Vec<u8>>>();
+    }
+
+    #[test]
+    fn test_basic_operations() {
+        let table = ShardedHashTable::with_shards(4);
+        table.insert("a".to_string(), 1);
+        assert_eq!(table.insert("b".to_string(), 0), None);
+        assert_eq!(table.insert("b".to_string(), 2), Some(0));
+
+        assert_eq!(table.shard_count(), 4);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get("a").map(|value| *value), Some(1));
+        assert_eq!(table.get_cloned("b"), Some(2));
+        assert_eq!(table.update("b", |value| *value += 10), Some(()));
+        assert_eq!(table.remove("b"), Some(12));
+        assert!(table.get("b").is_none());
+    }
+
+    #[test]
+    fn test_concurrent_stress() {
+        const THREADS: usize = 8;
+        const KEYS: usize = 2000;
+
+        let table = ShardedHashTable::with_shards(16);
+        let hits = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let (table, hits) = (&table, &hits);
+                scope.spawn(move || {
+                    for n in 0..KEYS {
+                        let key = thread * KEYS + n;
+                        table.insert(key, n);
+                        if table.get_cloned(&key) == Some(n) {
+                            hits.fetch_add(1, Ordering::Relaxed);
+                        }
+                        if n % 2 == 1 {
+                            assert_eq!(table.remove(&key), Some(n));
+                        }
+                        table.update(&(key / 2), |value| *value += 0);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(hits.load(Ordering::Relaxed), THREADS * KEYS);
+        assert_eq!(table.len(), THREADS * KEYS / 2);
+        for key in (0..THREADS * KEYS).step_by(2) {
+            assert_eq!(table.get_cloned(&key), Some(key % KEYS));
+        }
+    }
+
+    #[test]
+    fn test_poisoned_shard_stays_usable() {
+        let table = ShardedHashTable::with_shards(1);
+        table.insert(1, 1);
+
+        let result = thread::scope(|scope| {
+            scope
+                .spawn(|| table.update(&1, |_| panic!("panicking update")))
+                .join()
+        });
+        assert!(result.is_err());
+
+        table.insert(2, 2);
+        assert_eq!(table.get_cloned(&1), Some(1));
+        assert_eq!(table.len(), 2);
+    }
+
+    #[cfg(feature = "entry-metadata")]
+    #[test]
+    fn test_update_updates_metadata() {
+        let table: ShardedHashTable<u32, u32> = ShardedHashTable::new();
+        table.insert(1, 1);
+        let metadata = || table.read(table.shard_for(&1)).entry_metadata(&1).unwrap();
+        let before = metadata();
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        table.update(&1, |value| *value += 1);
+        assert!(metadata().last_modified > before.last_modified);
+    }
+
+    #[cfg(feature = "entry-metadata")]
+    #[test]
+    fn test_get_counts_as_access() {
+        let table: ShardedHashTable<u32, u32> = ShardedHashTable::new();
+        table.insert(1, 1);
+        assert_eq!(*table.get(&1).unwrap(), 1);
+        table.get_cloned(&1);
+
+        let shard = table.read(table.shard_for(&1));
+        assert_eq!(shard.entry_metadata(&1).unwrap().access_count, 2);
+    }
+}
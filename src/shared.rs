@@ -0,0 +1,154 @@
+//! A table that can be read and written through shared references on one
+//! thread.
+
+use std::{borrow::Borrow, cell::RefCell, hash::Hash, rc::Rc};
+
+use crate::HashTable;
+
+/// A single-threaded table with `&self` methods and a `RefCell` per value.
+///
+/// The table itself is only borrowed for the duration of each call, never
+/// while user code runs, so a closure passed to [`with_mut`](Self::with_mut)
+/// may freely read other entries or insert new ones. Borrow conflicts are
+/// tracked per entry: only mutably borrowing a value that is already
+/// borrowed panics.
+pub struct SharedTable<K, V> {
+    table: RefCell<HashTable<K, Rc<RefCell<V>>>>,
+}
+
+impl<K, V> SharedTable<K, V>
+where
+    K: Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            table: RefCell::new(HashTable::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts `value` under `key` and returns the value it displaced, if
+    /// any. Replacing a value writes into the existing cell, so handles from
+    /// [`get`](Self::get) see the new value; panics if that value is
+    /// currently borrowed.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let existing = self.table.borrow().get(&key).cloned();
+        match existing {
+            Some(cell) => Some(cell.replace(value)),
+            None => {
+                self.table
+                    .borrow_mut()
+                    .insert(key, Rc::new(RefCell::new(value)));
+                None
+            }
+        }
+    }
+
+    /// A handle to `key`'s value cell, which stays usable even if the entry
+    /// is later removed.
+    pub fn get<Q>(&self, key: &Q) -> Option<Rc<RefCell<V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.borrow().get(key).cloned()
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.borrow().contains_key(key)
+    }
+
+    /// Calls `f` with a shared borrow of `key`'s value.
+    pub fn with<Q, R>(&self, key: &Q, f: impl FnOnce(&V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let cell = self.get(key)?;
+        let value = RefCell::borrow(&cell);
+        Some(f(&value))
+    }
+
+    /// Calls `f` with a mutable borrow of `key`'s value. Panics if the value
+    /// is already borrowed, e.g. by an enclosing `with` on the same key.
+    pub fn with_mut<Q, R>(&self, key: &Q, f: impl FnOnce(&mut V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let cell = self.get(key)?;
+        let mut value = cell.borrow_mut();
+        Some(f(&mut value))
+    }
+
+    /// Removes `key`'s entry and returns its value cell, which outstanding
+    /// handles may still share.
+    pub fn remove<Q>(&self, key: &Q) -> Option<Rc<RefCell<V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.borrow_mut().remove(key)
+    }
+}
+
+impl<K, V> Default for SharedTable<K, V>
+where
+    K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[test]
+    fn test_nested_access() {
+        let sprites: SharedTable<&str, (i32, i32)> = SharedTable::new();
+        sprites.insert("player", (0, 0));
+        sprites.insert("enemy", (10, 5));
+
+        sprites.with_mut("player", |player| {
+            let enemy = sprites.with("enemy", |enemy| *enemy).unwrap();
+            player.0 = enemy.0 - 1;
+            sprites.insert("bullet", (player.0, player.1));
+        });
+
+        assert_eq!(sprites.with("player", |p| *p), Some((9, 0)));
+        assert_eq!(sprites.len(), 3);
+        assert_eq!(sprites.insert("enemy", (11, 5)), Some((10, 5)));
+        assert_eq!(*RefCell::borrow(&sprites.remove("bullet").unwrap()), (9, 0));
+        assert!(!sprites.contains_key("bullet"));
+    }
+
+    #[test]
+    fn test_conflicting_borrow_of_one_entry_panics() {
+        let counters: SharedTable<u32, u32> = SharedTable::new();
+        counters.insert(1, 0);
+
+        let handle = counters.get(&1).unwrap();
+        let reading = RefCell::borrow(&handle);
+        let result = catch_unwind(AssertUnwindSafe(|| counters.with_mut(&1, |n| *n += 1)));
+        assert!(result.is_err());
+        drop(reading);
+
+        counters.with_mut(&1, |n| *n += 1);
+        assert_eq!(*RefCell::borrow(&handle), 1);
+    }
+}
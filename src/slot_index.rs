@@ -0,0 +1,112 @@
+//! Resolving a key to its slot once and revisiting the entry without
+//! hashing again.
+
+use std::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::HashTable;
+
+/// The slot an entry occupied when [`HashTable::find_index`] found it.
+///
+/// Inserting a new key without growing the table leaves existing entries
+/// where they are, so the index stays valid. Removals, resizes and other
+/// operations that can move entries invalidate it, after which
+/// [`HashTable::get_at`] returns `None`. An index only makes sense for the
+/// table that produced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotIndex {
+    slot: usize,
+    generation: u64,
+}
+
+impl SlotIndex {
+    pub fn slot(self) -> usize {
+        self.slot
+    }
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    pub fn find_index<Q>(&self, key: &Q) -> Option<SlotIndex>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        Some(SlotIndex {
+            slot: self.find_slot(key)?,
+            generation: self.generation,
+        })
+    }
+
+    /// The entry at `index`, or `None` if entries may have moved since it
+    /// was found. Never hashes.
+    pub fn get_at(&self, index: SlotIndex) -> Option<(&K, &V)> {
+        if index.generation != self.generation {
+            return None;
+        }
+        let bucket = self.slots.get(index.slot)?.as_ref()?;
+        Some((&bucket.key, &bucket.value))
+    }
+
+    pub fn get_at_mut(&mut self, index: SlotIndex) -> Option<(&K, &mut V)> {
+        if index.generation != self.generation {
+            return None;
+        }
+        let bucket = self.slots.get_mut(index.slot)?.as_mut()?;
+        Some(bucket.key_value_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_index_survives_plain_inserts() {
+        let mut table: HashTable<&str, u32> = HashTable::with_capacity(64);
+        table.insert("hits", 0);
+        let hits = table.find_index("hits").unwrap();
+
+        for _ in 0..10 {
+            *table.get_at_mut(hits).unwrap().1 += 1;
+        }
+        table.insert("misses", 0);
+        assert_eq!(table.get_at(hits), Some((&"hits", &10)));
+        assert_eq!(table.find_index("absent"), None);
+    }
+
+    #[test]
+    fn test_slot_index_invalidated_by_moves() {
+        let mut table: HashTable<u32, u32> = (0..8).map(|n| (n, n)).collect();
+
+        let index = table.find_index(&3).unwrap();
+        table.remove(&5);
+        assert_eq!(table.get_at(index), None);
+
+        let index = table.find_index(&3).unwrap();
+        table.reserve(1000);
+        assert_eq!(table.get_at(index), None);
+
+        let index = table.find_index(&3).unwrap();
+        table.clear();
+        assert_eq!(table.get_at_mut(index), None);
+    }
+
+    #[cfg(feature = "entry-metadata")]
+    #[test]
+    fn test_get_at_mut_updates_metadata() {
+        let mut table: HashTable<u32, u32> = HashTable::new();
+        table.insert(1, 1);
+        let before = table.entry_metadata(&1).unwrap();
+        let index = table.find_index(&1).unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        *table.get_at_mut(index).unwrap().1 += 1;
+        assert!(table.entry_metadata(&1).unwrap().last_modified > before.last_modified);
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    collections::BTreeMap,
+    hash::Hash,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::HashTable;
+
+#[derive(Clone)]
+struct Indexed<V, O> {
+    value: V,
+    order: O,
+    seq: u64,
+}
+
+/// A [`HashTable`] with a secondary index ordered by a key extracted from
+/// each value.
+///
+/// The index is updated on every insert and remove, so range and top-N
+/// queries over the extracted key never scan the whole table. Values can
+/// only be changed by re-inserting them, which keeps the index honest.
+pub struct SortedIndexTable<K, V, O, F>
+where
+    K: Eq + Hash + Clone,
+    O: Ord + Clone,
+    F: Fn(&V) -> O,
+{
+    entries: HashTable<K, Indexed<V, O>>,
+    index: BTreeMap<(O, u64), K>,
+    extract: F,
+    next_seq: u64,
+}
+
+impl<K, V, O, F> SortedIndexTable<K, V, O, F>
+where
+    K: Eq + Hash + Clone,
+    O: Ord + Clone,
+    F: Fn(&V) -> O,
+{
+    pub fn new(extract: F) -> Self {
+        Self {
+            entries: HashTable::new(),
+            index: BTreeMap::new(),
+            extract,
+            next_seq: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.remove(&key);
+
+        let order = (self.extract)(&value);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        self.index.insert((order.clone(), seq), key.clone());
+        self.entries.insert(key, Indexed { value, order, seq });
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).map(|entry| &entry.value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let entry = self.entries.remove(key)?;
+        self.index.remove(&(entry.order, entry.seq));
+        Some(entry.value)
+    }
+
+    /// Entries whose extracted key falls in `range`, in ascending order.
+    /// Entries with equal extracted keys come out in insertion order.
+    pub fn range<R: RangeBounds<O>>(&self, range: R) -> impl DoubleEndedIterator<Item = (&K, &V)> {
+        let start = match range.start_bound() {
+            Bound::Included(order) => Bound::Included((order.clone(), u64::MIN)),
+            Bound::Excluded(order) => Bound::Excluded((order.clone(), u64::MAX)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(order) => Bound::Included((order.clone(), u64::MAX)),
+            Bound::Excluded(order) => Bound::Excluded((order.clone(), u64::MIN)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+
+        self.index
+            .range((start, end))
+            .map(|(_, key)| (key, &self.entries.get(key).unwrap().value))
+    }
+
+    /// The `n` entries with the largest extracted keys, largest first.
+    pub fn top(&self, n: usize) -> impl Iterator<Item = (&K, &V)> {
+        self.range(..).rev().take(n)
+    }
+
+    /// The `n` entries with the smallest extracted keys, smallest first.
+    pub fn bottom(&self, n: usize) -> impl Iterator<Item = (&K, &V)> {
+        self.range(..).take(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Session {
+        idle_secs: u32,
+    }
+
+    fn idle(secs: u32) -> Session {
+        Session { idle_secs: secs }
+    }
+
+    #[test]
+    fn test_range_and_top_n() {
+        let mut sessions = SortedIndexTable::new(|session: &Session| session.idle_secs);
+        sessions.insert("a", idle(5));
+        sessions.insert("b", idle(120));
+        sessions.insert("c", idle(60));
+        sessions.insert("d", idle(60));
+        sessions.insert("e", idle(900));
+
+        let idle_long: Vec<&str> = sessions.range(60..).map(|(key, _)| *key).collect();
+        assert_eq!(idle_long, vec!["c", "d", "b", "e"]);
+
+        let between: Vec<&str> = sessions.range(6..=120).map(|(key, _)| *key).collect();
+        assert_eq!(between, vec!["c", "d", "b"]);
+
+        let top: Vec<&str> = sessions.top(2).map(|(key, _)| *key).collect();
+        assert_eq!(top, vec!["e", "b"]);
+        let bottom: Vec<&str> = sessions.bottom(1).map(|(key, _)| *key).collect();
+        assert_eq!(bottom, vec!["a"]);
+    }
+
+    #[test]
+    fn test_index_follows_updates_and_removals() {
+        let mut sessions = SortedIndexTable::new(|session: &Session| session.idle_secs);
+        sessions.insert("a", idle(5));
+        sessions.insert("b", idle(10));
+
+        sessions.insert("a", idle(50));
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions.index.len(), 2);
+        assert_eq!(sessions.top(1).next(), Some((&"a", &idle(50))));
+
+        assert_eq!(sessions.remove(&"a"), Some(idle(50)));
+        assert_eq!(sessions.range(..).count(), 1);
+        assert_eq!(sessions.get(&"b"), Some(&idle(10)));
+        assert_eq!(sessions.remove(&"missing"), None);
+    }
+}
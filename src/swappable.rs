@@ -0,0 +1,169 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, PoisonError, RwLock,
+};
+
+use crate::HashTable;
+
+/// A shared, read-mostly table whose contents are replaced wholesale.
+///
+/// Readers take a snapshot with [`load`](Self::load) and query it without
+/// any further synchronization; a writer builds the next generation on its
+/// own and publishes it with [`store`](Self::store). Readers holding an older
+/// snapshot keep seeing it until they drop it.
+///
+/// The lock guards only the `Arc` pointer, so it is held just long enough to
+/// clone or replace it, never while a table is queried or dropped. Hot read
+/// paths should go through a [`SwappableReader`], which takes no lock at all
+/// unless a new generation has been published since its last load.
+pub struct SwappableTable<K, V> {
+    current: RwLock<Arc<HashTable<K, V>>>,
+    /// Bumped under the write lock each time a generation is published.
+    version: AtomicU64,
+}
+
+/// A per-thread handle on a [`SwappableTable`] that caches the current
+/// generation. Created with [`SwappableTable::reader`].
+pub struct SwappableReader<'a, K, V> {
+    source: &'a SwappableTable<K, V>,
+    version: u64,
+    cached: Arc<HashTable<K, V>>,
+}
+
+impl<K, V> SwappableReader<'_, K, V> {
+    /// Returns the currently published generation. Only an atomic load
+    /// unless the generation changed since the last call.
+    pub fn load(&mut self) -> &Arc<HashTable<K, V>> {
+        if self.source.version.load(Ordering::Acquire) != self.version {
+            let current = self.source.read();
+            self.version = self.source.version.load(Ordering::Acquire);
+            self.cached = Arc::clone(&current);
+        }
+        &self.cached
+    }
+}
+
+impl<K, V> SwappableTable<K, V> {
+    pub fn new(table: HashTable<K, V>) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(table)),
+            version: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the currently published generation.
+    pub fn load(&self) -> Arc<HashTable<K, V>> {
+        Arc::clone(&self.read())
+    }
+
+    /// Returns a handle whose loads avoid the lock between publishes.
+    pub fn reader(&self) -> SwappableReader<'_, K, V> {
+        let current = self.read();
+        SwappableReader {
+            source: self,
+            version: self.version.load(Ordering::Acquire),
+            cached: Arc::clone(&current),
+        }
+    }
+
+    /// Publishes `table` as the new generation and returns the previous one.
+    pub fn store(&self, table: HashTable<K, V>) -> Arc<HashTable<K, V>> {
+        let next = Arc::new(table);
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        self.version.fetch_add(1, Ordering::Release);
+        std::mem::replace(&mut *current, next)
+    }
+
+    /// Builds the next generation from the current one and publishes it.
+    /// Concurrent `update` calls are not serialized against each other; the
+    /// last one to publish wins.
+    pub fn update(&self, f: impl FnOnce(&HashTable<K, V>) -> HashTable<K, V>) {
+        let next = f(&self.load());
+        self.store(next);
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Arc<HashTable<K, V>>> {
+        // The guarded value is a plain pointer that is never left half
+        // written, so a poisoned lock is safe to keep using.
+        self.current.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_load_and_store() {
+        let mut first = HashTable::new();
+        first.insert("mode", "old");
+        let table = SwappableTable::new(first);
+
+        let snapshot = table.load();
+        let mut second = HashTable::new();
+        second.insert("mode", "new");
+        let previous = table.store(second);
+
+        assert_eq!(snapshot.get(&"mode"), Some(&"old"));
+        assert_eq!(previous.get(&"mode"), Some(&"old"));
+        assert_eq!(table.load().get(&"mode"), Some(&"new"));
+
+        table.update(|current| {
+            let mut next = current.try_clone().unwrap();
+            next.insert("extra", "yes");
+            next
+        });
+        assert_eq!(table.load().get(&"extra"), Some(&"yes"));
+    }
+
+    #[test]
+    fn test_reader_skips_lock_between_publishes() {
+        let table = SwappableTable::new(HashTable::from_iter([("mode", "old")]));
+        let mut reader = table.reader();
+        assert_eq!(reader.load().get(&"mode"), Some(&"old"));
+
+        {
+            // A reader that took the lock here would deadlock.
+            let _writer = table.current.write().unwrap();
+            assert_eq!(reader.load().get(&"mode"), Some(&"old"));
+        }
+
+        table.store(HashTable::from_iter([("mode", "new")]));
+        assert_eq!(reader.load().get(&"mode"), Some(&"new"));
+    }
+
+    #[test]
+    fn test_readers_see_whole_generations() {
+        let table = Arc::new(SwappableTable::new(HashTable::<u32, u32>::new()));
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let table = Arc::clone(&table);
+                thread::spawn(move || {
+                    let mut reader = table.reader();
+                    for _ in 0..1000 {
+                        let snapshot = reader.load();
+                        if let Some(generation) = snapshot.get(&0) {
+                            for key in 1..10 {
+                                assert_eq!(snapshot.get(&key), Some(generation));
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for generation in 0..100 {
+            let mut next = HashTable::new();
+            for key in 0..10 {
+                next.insert(key, generation);
+            }
+            table.store(next);
+        }
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! A table whose entries expire at per-key deadlines, scheduled on a hashed
+//! timing wheel.
+
+use std::hash::Hash;
+
+use crate::HashTable;
+
+#[derive(Clone)]
+struct Timer<V> {
+    value: V,
+    deadline: u64,
+    wheel_slot: usize,
+    position: usize,
+}
+
+/// Pairs a [`HashTable`] with a hashed timing wheel so that entries can be
+/// given a deadline, measured in abstract ticks, and collected once it
+/// passes.
+///
+/// Scheduling, rescheduling and cancelling are O(1). Advancing the clock
+/// visits one wheel slot per elapsed tick (at most one full turn of the
+/// wheel), and only the entries in those slots are examined.
+pub struct TimerTable<K, V> {
+    entries: HashTable<K, Timer<V>>,
+    wheel: Vec<Vec<K>>,
+    now: u64,
+}
+
+impl<K, V> TimerTable<K, V>
+where
+    K: Eq + Hash + Clone,
+{
+    /// Creates a table with a wheel of `wheel_size` slots, starting at
+    /// tick 0. Deadlines further out than one wheel turn are fine; they are
+    /// skipped over on each turn until due.
+    ///
+    /// Panics if `wheel_size` is zero.
+    pub fn new(wheel_size: usize) -> Self {
+        assert!(wheel_size > 0, "timing wheel needs at least one slot");
+
+        Self {
+            entries: HashTable::new(),
+            wheel: vec![Vec::new(); wheel_size],
+            now: 0,
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts `key` to expire at `deadline`, replacing any existing entry
+    /// and its schedule. Deadlines that have already passed fire on the next
+    /// call to [`advance`](Self::advance).
+    pub fn insert(&mut self, key: K, value: V, deadline: u64) {
+        self.remove(&key);
+
+        let wheel_slot = self.wheel_slot_for(deadline);
+        let position = self.wheel[wheel_slot].len();
+        self.wheel[wheel_slot].push(key.clone());
+        self.entries.insert(
+            key,
+            Timer {
+                value,
+                deadline,
+                wheel_slot,
+                position,
+            },
+        );
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).map(|timer| &timer.value)
+    }
+
+    pub fn deadline(&self, key: &K) -> Option<u64> {
+        self.entries.get(key).map(|timer| timer.deadline)
+    }
+
+    /// Moves `key`'s deadline. Returns `false` if the key is not present.
+    pub fn reschedule(&mut self, key: &K, deadline: u64) -> bool {
+        match self.remove(key) {
+            Some(value) => {
+                self.insert(key.clone(), value, deadline);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes `key` and cancels its timer.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let timer = self.entries.remove(key)?;
+        self.unlink(timer.wheel_slot, timer.position);
+        Some(timer.value)
+    }
+
+    /// Moves the clock forward to tick `now` and returns every entry whose
+    /// deadline is at or before it, in tick order. Entries sharing a
+    /// deadline come out in no particular order.
+    pub fn advance(&mut self, now: u64) -> Vec<(K, V)> {
+        if now <= self.now {
+            return Vec::new();
+        }
+
+        let mut expired = Vec::new();
+        let elapsed = now - self.now;
+        let turns = elapsed.min(self.wheel.len() as u64);
+        for step in 1..=turns {
+            let wheel_slot = ((self.now + step) % self.wheel.len() as u64) as usize;
+            self.expire_slot(wheel_slot, now, &mut expired);
+        }
+
+        self.now = now;
+        // A slot holds deadlines from several turns of the wheel, so slot
+        // order is only tick order within one turn.
+        expired.sort_by_key(|&(deadline, _, _)| deadline);
+        expired
+            .into_iter()
+            .map(|(_, key, value)| (key, value))
+            .collect()
+    }
+
+    fn expire_slot(&mut self, wheel_slot: usize, now: u64, expired: &mut Vec<(u64, K, V)>) {
+        let mut position = 0;
+        while position < self.wheel[wheel_slot].len() {
+            let key = &self.wheel[wheel_slot][position];
+            let deadline = self.entries.get(key).unwrap().deadline;
+
+            if deadline <= now {
+                let key = key.clone();
+                let timer = self.entries.remove(&key).unwrap();
+                self.unlink(wheel_slot, position);
+                expired.push((deadline, key, timer.value));
+            } else {
+                position += 1;
+            }
+        }
+    }
+
+    fn wheel_slot_for(&self, deadline: u64) -> usize {
+        let due = deadline.max(self.now + 1);
+        (due % self.wheel.len() as u64) as usize
+    }
+
+    /// Removes the key at `position` from `wheel_slot` by swapping the last
+    /// key into its place, and records the moved key's new position.
+    fn unlink(&mut self, wheel_slot: usize, position: usize) {
+        let slot = &mut self.wheel[wheel_slot];
+        slot.swap_remove(position);
+
+        if let Some(moved) = slot.get(position) {
+            let index = self.entries.find_slot(moved).unwrap();
+            self.entries.slots[index].as_mut().unwrap().value.position = position;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(mut expired: Vec<(u32, &str)>) -> Vec<u32> {
+        expired.sort();
+        expired.into_iter().map(|(key, _)| key).collect()
+    }
+
+    #[test]
+    fn test_entries_expire_at_deadline() {
+        let mut timers: TimerTable<u32, &str> = TimerTable::new(8);
+        timers.insert(1, "one", 3);
+        timers.insert(2, "two", 5);
+        timers.insert(3, "three", 5);
+        timers.insert(4, "far", 30);
+
+        assert!(timers.advance(2).is_empty());
+        assert_eq!(timers.advance(3), vec![(1, "one")]);
+        assert_eq!(keys(timers.advance(10)), vec![2, 3]);
+        assert_eq!(timers.len(), 1);
+
+        assert!(timers.advance(29).is_empty());
+        assert_eq!(timers.advance(100), vec![(4, "far")]);
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn test_reschedule_and_remove() {
+        let mut timers: TimerTable<u32, &str> = TimerTable::new(4);
+        for key in 0..6 {
+            timers.insert(key, "timer", 2);
+        }
+
+        assert!(timers.reschedule(&0, 9));
+        assert_eq!(timers.deadline(&0), Some(9));
+        assert!(!timers.reschedule(&42, 9));
+        assert_eq!(timers.remove(&3), Some("timer"));
+
+        assert_eq!(keys(timers.advance(2)), vec![1, 2, 4, 5]);
+        assert_eq!(timers.get(&0), Some(&"timer"));
+        assert_eq!(timers.advance(9), vec![(0, "timer")]);
+    }
+
+    #[test]
+    fn test_past_deadline_fires_on_next_advance() {
+        let mut timers: TimerTable<u32, &str> = TimerTable::new(16);
+        timers.advance(50);
+        timers.insert(7, "late", 10);
+
+        assert_eq!(timers.advance(51), vec![(7, "late")]);
+    }
+
+    #[test]
+    fn test_advance_returns_tick_order_across_turns() {
+        let mut timers: TimerTable<u32, &str> = TimerTable::new(8);
+        timers.insert(1, "nine", 9);
+        timers.insert(2, "three", 3);
+        timers.insert(3, "twenty", 20);
+
+        assert_eq!(
+            timers.advance(100),
+            vec![(2, "three"), (1, "nine"), (3, "twenty")]
+        );
+    }
+}
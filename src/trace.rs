@@ -0,0 +1,213 @@
+//! Opt-in operation recording and deterministic replay.
+//!
+//! A [`Recorder`] wraps a table and logs every insert, get and remove as the
+//! 64-bit hash of the key involved. The resulting [`Trace`] contains no keys
+//! or values, so it can be shared from environments where the data itself
+//! cannot, and [`Trace::replay`] rebuilds a table with the same slot layout.
+
+use std::{cell::RefCell, hash::Hash};
+
+use crate::{HashTable, HashTableBuilder};
+
+const TAG_INSERT: u8 = 0;
+const TAG_GET: u8 = 1;
+const TAG_REMOVE: u8 = 2;
+const ENCODED_OP_LEN: usize = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Insert(u64),
+    Get(u64),
+    Remove(u64),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    ops: Vec<Op>,
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    pub fn push(&mut self, op: Op) {
+        self.ops.push(op);
+    }
+
+    /// Encodes the trace as one tag byte plus a little-endian hash per
+    /// operation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.ops.len() * ENCODED_OP_LEN);
+        for op in &self.ops {
+            let (tag, hash) = match *op {
+                Op::Insert(hash) => (TAG_INSERT, hash),
+                Op::Get(hash) => (TAG_GET, hash),
+                Op::Remove(hash) => (TAG_REMOVE, hash),
+            };
+            bytes.push(tag);
+            bytes.extend_from_slice(&hash.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes a trace produced by [`to_bytes`](Self::to_bytes), returning
+    /// `None` if the input is truncated or contains an unknown tag.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if !bytes.len().is_multiple_of(ENCODED_OP_LEN) {
+            return None;
+        }
+
+        let mut ops = Vec::with_capacity(bytes.len() / ENCODED_OP_LEN);
+        for chunk in bytes.chunks_exact(ENCODED_OP_LEN) {
+            let hash = u64::from_le_bytes(chunk[1..].try_into().unwrap());
+            let op = match chunk[0] {
+                TAG_INSERT => Op::Insert(hash),
+                TAG_GET => Op::Get(hash),
+                TAG_REMOVE => Op::Remove(hash),
+                _ => return None,
+            };
+            ops.push(op);
+        }
+
+        Some(Self { ops })
+    }
+
+    /// Replays the trace against an empty default table. Use
+    /// [`replay_with`](Self::replay_with) if the recorded table was built
+    /// with a non-default capacity or load factor.
+    ///
+    /// Each recorded hash stands in for the key it came from, and entries are
+    /// placed by that hash, so the returned table has the same slot layout,
+    /// probe chains and resizes as the recorded one. Because the keys are
+    /// hashes, look entries up through the diagnostics APIs
+    /// (`check_integrity`, `collision_report`, `to_dot`) rather than `get`.
+    /// Distinct keys with identical 64-bit hashes collapse into one entry.
+    pub fn replay(&self) -> HashTable<u64, ()> {
+        self.replay_with(HashTableBuilder::new())
+    }
+
+    pub fn replay_with(&self, builder: HashTableBuilder) -> HashTable<u64, ()> {
+        let mut table = builder.build();
+
+        for op in &self.ops {
+            match *op {
+                Op::Insert(hash) => {
+                    if table.find_slot_hashed(hash, |key| *key == hash).is_none() {
+                        table.insert_new(hash, hash, ());
+                    }
+                }
+                Op::Get(_) => {}
+                Op::Remove(hash) => {
+                    if let Some(index) = table.find_slot_hashed(hash, |key| *key == hash) {
+                        table.remove_at(index);
+                    }
+                }
+            }
+        }
+
+        table
+    }
+}
+
+/// A table wrapper that records every operation into a [`Trace`].
+pub struct Recorder<K, V> {
+    table: HashTable<K, V>,
+    trace: RefCell<Trace>,
+}
+
+impl<K, V> Recorder<K, V>
+where
+    K: Eq + Hash,
+{
+    pub fn new(table: HashTable<K, V>) -> Self {
+        Self {
+            table,
+            trace: RefCell::new(Trace::new()),
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.trace
+            .get_mut()
+            .push(Op::Insert(self.table.make_hash(&key)));
+        self.table.insert(key, value);
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.trace
+            .borrow_mut()
+            .push(Op::Get(self.table.make_hash(key)));
+        self.table.get(key)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.trace
+            .get_mut()
+            .push(Op::Remove(self.table.make_hash(key)));
+        self.table.remove(key)
+    }
+
+    pub fn table(&self) -> &HashTable<K, V> {
+        &self.table
+    }
+
+    pub fn trace(&self) -> Trace {
+        self.trace.borrow().clone()
+    }
+
+    pub fn into_parts(self) -> (HashTable<K, V>, Trace) {
+        (self.table, self.trace.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_round_trips_through_bytes() {
+        let mut trace = Trace::new();
+        trace.push(Op::Insert(1));
+        trace.push(Op::Get(u64::MAX));
+        trace.push(Op::Remove(42));
+
+        let bytes = trace.to_bytes();
+        assert_eq!(bytes.len(), 27);
+        assert_eq!(Trace::from_bytes(&bytes), Some(trace));
+
+        assert_eq!(Trace::from_bytes(&bytes[..26]), None);
+        assert_eq!(Trace::from_bytes(&[7; 9]), None);
+    }
+
+    #[test]
+    fn test_replay_reproduces_slot_layout() {
+        let mut recorder = Recorder::new(HashTable::new());
+        for i in 0..40 {
+            recorder.insert(format!("key{i}"), i);
+        }
+        for i in (0..40).step_by(7) {
+            recorder.get(&format!("key{i}"));
+        }
+        recorder.insert("key3".to_string(), 300);
+        recorder.remove(&"key5".to_string());
+
+        let (table, trace) = recorder.into_parts();
+        assert_eq!(trace.ops().len(), 40 + 6 + 2);
+
+        let replayed = Trace::from_bytes(&trace.to_bytes()).unwrap().replay();
+        assert_eq!(replayed.size, table.size);
+        assert_eq!(replayed.slots.len(), table.slots.len());
+        for (original, copy) in table.slots.iter().zip(&replayed.slots) {
+            assert_eq!(
+                original.as_ref().map(|bucket| bucket.hash),
+                copy.as_ref().map(|bucket| bucket.hash)
+            );
+        }
+        assert_eq!(replayed.check_integrity(), Ok(()));
+    }
+}
@@ -0,0 +1,425 @@
+//! Whole-table operations that move entries between tables.
+
+use std::{
+    hash::{BuildHasher, Hash},
+    mem,
+};
+
+use crate::{
+    empty_slots, slots_for, Bucket, DuplicateKeyError, HashTable, ProbeMonitor, Shadow,
+    INITIAL_CAPACITY,
+};
+
+type Merger<'a, V> = Box<dyn FnMut(&mut V, V) + 'a>;
+
+/// What to do when an operation produces a second entry for a key that is
+/// already present.
+pub enum DuplicatePolicy<'a, V> {
+    /// Keep the value that arrived first.
+    KeepFirst,
+    /// Overwrite with the value that arrived last.
+    KeepLast,
+    /// Fail with a [`DuplicateKeyError`].
+    Reject,
+    /// Fold the later value into the earlier one.
+    Merge(Merger<'a, V>),
+}
+
+impl<'a, V> DuplicatePolicy<'a, V> {
+    pub fn merge(f: impl FnMut(&mut V, V) + 'a) -> Self {
+        DuplicatePolicy::Merge(Box::new(f))
+    }
+
+    /// Resolves `incoming` against the entry in `existing`, returning
+    /// `false` if the policy rejects duplicates.
+    pub(crate) fn resolve<K>(&mut self, existing: &mut Bucket<K, V>, incoming: V) -> bool {
+        match self {
+            DuplicatePolicy::KeepFirst => {}
+            DuplicatePolicy::KeepLast => *existing.value_mut() = incoming,
+            DuplicatePolicy::Reject => return false,
+            DuplicatePolicy::Merge(f) => f(existing.value_mut(), incoming),
+        }
+        true
+    }
+}
+
+impl<K, V, S> HashTable<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Clone,
+{
+    /// Splits the table in two: entries for which `pred` returns `true`, and
+    /// the rest.
+    pub fn partition(mut self, pred: impl FnMut(&K, &V) -> bool) -> (Self, Self) {
+        let matching = self.split_off_by(pred);
+        (matching, self)
+    }
+
+    /// Moves every entry for which `pred` returns `true` into a new table
+    /// and returns it. Entries keep their cached hashes, so nothing is
+    /// rehashed.
+    /// `pred` sees every entry before any is moved, so if it panics the
+    /// table is left as it was.
+    pub fn split_off_by(&mut self, mut pred: impl FnMut(&K, &V) -> bool) -> Self {
+        let selected: Vec<bool> = self
+            .slots
+            .iter()
+            .map(|slot| {
+                slot.as_ref()
+                    .is_some_and(|bucket| pred(&bucket.key, &bucket.value))
+            })
+            .collect();
+        let count = selected.iter().filter(|&&selected| selected).count();
+        self.split_off_selected(&selected, count)
+    }
+
+    pub fn split_off_keys(&mut self, keys: impl IntoIterator<Item = K>) -> Self {
+        let mut selected = vec![false; self.slots.len()];
+        let mut count = 0;
+        for key in keys {
+            if let Some(index) = self.find_slot(&key) {
+                if !selected[index] {
+                    selected[index] = true;
+                    count += 1;
+                }
+            }
+        }
+
+        self.split_off_selected(&selected, count)
+    }
+
+    pub fn map_values<V2>(self, mut f: impl FnMut(&K, V) -> V2) -> HashTable<K, V2, S> {
+        let slots = self
+            .slots
+            .into_iter()
+            .map(|slot| {
+                slot.map(|bucket| Bucket {
+                    value: f(&bucket.key, bucket.value),
+                    hash: bucket.hash,
+                    key: bucket.key,
+                    meta: bucket.meta,
+                    id: bucket.id,
+                })
+            })
+            .collect();
+
+        let mut mapped = HashTable {
+            slots,
+            size: self.size,
+            load_factor: self.load_factor,
+            probing: self.probing,
+            salt: self.salt,
+            generation: self.generation,
+            shadow: Shadow::new(),
+            probes: ProbeMonitor::new(),
+            hash_builder: self.hash_builder,
+        };
+        mapped.shadow = Shadow::mirror(&mapped);
+        mapped
+    }
+
+    /// Transforms every value, dropping entries for which `f` returns
+    /// `None`. Survivors are re-placed from their cached hashes so that the
+    /// dropped entries leave no gaps in probe chains.
+    pub fn filter_map_values<V2>(
+        self,
+        mut f: impl FnMut(&K, V) -> Option<V2>,
+    ) -> HashTable<K, V2, S> {
+        let mut mapped = self.sibling(self.slots.len());
+
+        for bucket in self.slots.into_iter().flatten() {
+            if let Some(value) = f(&bucket.key, bucket.value) {
+                mapped.insert_bucket(Bucket {
+                    hash: bucket.hash,
+                    key: bucket.key,
+                    value,
+                    meta: bucket.meta,
+                    id: bucket.id,
+                });
+            }
+        }
+
+        mapped.shadow.verify(&mapped);
+        mapped
+    }
+
+    /// Maps every key through `f` in a single pass into a table pre-sized
+    /// for the current entries. Keys that collide after mapping are
+    /// resolved by `policy`; under [`DuplicatePolicy::Reject`] the first
+    /// colliding mapped key is returned as the error.
+    pub fn rekey<K2>(
+        self,
+        mut f: impl FnMut(K) -> K2,
+        mut policy: DuplicatePolicy<'_, V>,
+    ) -> Result<HashTable<K2, V, S>, DuplicateKeyError<K2>>
+    where
+        K2: Eq + Hash,
+    {
+        let mut rekeyed = self.sibling(self.slots.len());
+
+        for (key, value) in self {
+            let key = f(key);
+            let hash = rekeyed.make_hash(&key);
+
+            match rekeyed.find_slot_hashed(hash, |stored| *stored == key) {
+                Some(index) => {
+                    let bucket = rekeyed.slots[index].as_mut().unwrap();
+                    if !policy.resolve(bucket, value) {
+                        return Err(DuplicateKeyError { key });
+                    }
+                }
+                None => {
+                    rekeyed.insert_new(hash, key, value);
+                }
+            }
+        }
+
+        rekeyed.shadow.verify(&rekeyed);
+        Ok(rekeyed)
+    }
+
+    /// Builds a histogram of entries by the class `classify` assigns them.
+    pub fn count_by<C>(&self, mut classify: impl FnMut(&K, &V) -> C) -> HashTable<C, usize, S>
+    where
+        C: Eq + Hash,
+    {
+        let mut counts = self.sibling(INITIAL_CAPACITY);
+
+        for bucket in self.slots.iter().flatten() {
+            let class = classify(&bucket.key, &bucket.value);
+            let hash = counts.make_hash(&class);
+
+            match counts.find_slot_hashed(hash, |stored| *stored == class) {
+                Some(index) => counts.slots[index].as_mut().unwrap().value += 1,
+                None => {
+                    counts.insert_new(hash, class, 1);
+                }
+            }
+        }
+
+        counts
+    }
+
+    /// Moves the `count` entries whose slots are marked in `selected` into
+    /// a new table. Runs no user code.
+    fn split_off_selected(&mut self, selected: &[bool], count: usize) -> Self {
+        let mut split = self.sibling(slots_for(count, self.load_factor));
+        if count == 0 {
+            return split;
+        }
+
+        let slot_count = self.slots.len();
+        let old_slots = mem::replace(&mut self.slots, empty_slots(slot_count));
+        self.size = 0;
+        self.generation += 1;
+
+        for (index, slot) in old_slots.into_iter().enumerate() {
+            let Some(bucket) = slot else {
+                continue;
+            };
+
+            if selected[index] {
+                self.shadow.remove(&bucket);
+                split.insert_bucket(bucket);
+            } else {
+                self.place(bucket);
+            }
+        }
+
+        self.shadow.verify(self);
+        split.shadow.verify(&split);
+        split
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        for i in 0..100 {
+            table.insert(i, i * 10);
+        }
+
+        let (even, odd) = table.partition(|key, _| key % 2 == 0);
+        assert_eq!(even.size, 50);
+        assert_eq!(odd.size, 50);
+        for i in 0..100 {
+            let (holder, other) = if i % 2 == 0 {
+                (&even, &odd)
+            } else {
+                (&odd, &even)
+            };
+            assert_eq!(holder.get(&i), Some(&(i * 10)));
+            assert_eq!(other.get(&i), None);
+        }
+        assert_eq!(even.check_integrity(), Ok(()));
+        assert_eq!(odd.check_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn test_split_off_by() {
+        let mut table: HashTable<&str, i32> = HashTable::new();
+        table.insert("small", 1);
+        table.insert("medium", 50);
+        table.insert("large", 100);
+
+        let big = table.split_off_by(|_, value| *value >= 50);
+        assert_eq!(big.size, 2);
+        assert_eq!(big.get(&"medium"), Some(&50));
+        assert_eq!(big.get(&"large"), Some(&100));
+
+        assert_eq!(table.size, 1);
+        assert_eq!(table.get(&"small"), Some(&1));
+        assert_eq!(table.get(&"large"), None);
+    }
+
+    #[test]
+    fn test_map_values() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        for i in 0..50 {
+            table.insert(i, i);
+        }
+        let layout: Vec<_> = table.buckets().collect();
+
+        let mapped = table.map_values(|key, value| format!("{key}:{}", value * 2));
+        assert_eq!(mapped.size, 50);
+        assert_eq!(mapped.get(&7).map(String::as_str), Some("7:14"));
+        assert_eq!(mapped.buckets().collect::<Vec<_>>(), layout);
+    }
+
+    #[test]
+    fn test_filter_map_values() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        for i in 0..50 {
+            table.insert(i, i);
+        }
+
+        let odd = table.filter_map_values(|_, value| (value % 2 == 1).then_some(value as u64));
+        assert_eq!(odd.size, 25);
+        assert_eq!(odd.get(&3), Some(&3));
+        assert_eq!(odd.get(&4), None);
+        assert_eq!(odd.check_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn test_count_by() {
+        let mut table: HashTable<i32, &str> = HashTable::new();
+        for i in 0..30 {
+            table.insert(i, if i < 10 { "low" } else { "high" });
+        }
+
+        let by_value = table.count_by(|_, value| *value);
+        assert_eq!(by_value.size, 2);
+        assert_eq!(by_value.get(&"low"), Some(&10));
+        assert_eq!(by_value.get(&"high"), Some(&20));
+
+        let by_remainder = table.count_by(|key, _| key % 3);
+        assert_eq!(by_remainder.get(&0), Some(&10));
+        assert_eq!(by_remainder.get(&1), Some(&10));
+        assert_eq!(by_remainder.get(&2), Some(&10));
+    }
+
+    #[test]
+    fn test_split_off_keys() {
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        for i in 0..100 {
+            table.insert(i, i);
+        }
+
+        let split = table.split_off_keys([3, 30, 3, 300]);
+        assert_eq!(split.size, 2);
+        assert_eq!(split.slots.len(), INITIAL_CAPACITY);
+        assert_eq!(split.get(&3), Some(&3));
+        assert_eq!(split.get(&30), Some(&30));
+
+        assert_eq!(table.size, 98);
+        assert_eq!(table.get(&3), None);
+        assert_eq!(table.get(&30), None);
+        assert_eq!(table.get(&31), Some(&31));
+        assert_eq!(table.check_integrity(), Ok(()));
+
+        let large = table.split_off_keys(0..60);
+        assert_eq!(large.size, 58);
+        assert_eq!(large.slots.len(), slots_for(58, large.load_factor));
+        assert_eq!(table.size, 40);
+    }
+
+    fn lengths() -> HashTable<String, usize> {
+        ["a", "bb", "cc", "ddd"]
+            .into_iter()
+            .map(|word| (word.to_string(), word.len()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rekey() {
+        let table = lengths().rekey(|word| word.to_uppercase(), DuplicatePolicy::Reject);
+        let table = table.unwrap();
+        assert_eq!(table.get(&"DDD".to_string()), Some(&3));
+        assert_eq!(table.get(&"ddd".to_string()), None);
+
+        let err = lengths().rekey(|word| word.len(), DuplicatePolicy::Reject);
+        assert_eq!(err.err(), Some(DuplicateKeyError { key: 2 }));
+
+        let summed = lengths()
+            .rekey(
+                |word| word.len(),
+                DuplicatePolicy::merge(|sum, n| *sum += n),
+            )
+            .unwrap();
+        assert_eq!(summed.size, 3);
+        assert_eq!(summed.get(&2), Some(&4));
+
+        let kept = lengths()
+            .rekey(|_| "all", DuplicatePolicy::KeepFirst)
+            .unwrap();
+        assert_eq!(kept.size, 1);
+    }
+
+    #[test]
+    fn test_panicking_predicate_leaves_table_intact() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut table: HashTable<i32, i32> = HashTable::new();
+        for i in 0..50 {
+            table.insert(i, i);
+        }
+
+        let mut calls = 0;
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            table.split_off_by(|_, _| {
+                calls += 1;
+                assert!(calls < 25, "predicate panicked");
+                true
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(table.size, 50);
+        assert!((0..50).all(|i| table.get(&i) == Some(&i)));
+        assert_eq!(table.check_integrity(), Ok(()));
+    }
+
+    #[cfg(feature = "entry-metadata")]
+    #[test]
+    fn test_rekey_merge_updates_metadata() {
+        let mut table: HashTable<u32, u32> = HashTable::new();
+        table.insert(1, 1);
+        table.insert(2, 2);
+
+        let merged = table
+            .rekey(
+                |_| {
+                    std::thread::sleep(std::time::Duration::from_millis(2));
+                    0
+                },
+                DuplicatePolicy::merge(|a, b| *a += b),
+            )
+            .unwrap();
+        let metadata = merged.entry_metadata(&0).unwrap();
+        assert_eq!(merged.get(&0), Some(&3));
+        assert!(metadata.last_modified > metadata.created_at);
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::VecDeque,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use crate::HashTable;
+
+/// A table split into fixed-width time buckets covering a sliding window.
+///
+/// Writes go to the newest bucket. When time moves past the newest bucket's
+/// end, a fresh bucket is started and the oldest one is dropped wholesale,
+/// so expiry costs nothing per entry. Queries see one value per live bucket
+/// and aggregate across them, e.g. summing per-bucket counters.
+///
+/// Time is passed in explicitly; mutating calls advance the window to the
+/// given instant, and [`advance`](Self::advance) does so without writing.
+pub struct WindowedTable<K, V> {
+    buckets: VecDeque<HashTable<K, V>>,
+    bucket_count: usize,
+    bucket_width: Duration,
+    newest_start: Instant,
+}
+
+impl<K, V> WindowedTable<K, V>
+where
+    K: Eq + Hash + Clone,
+{
+    /// A window of `bucket_count` buckets, each `bucket_width` long, whose
+    /// first bucket starts at `start`.
+    ///
+    /// Panics if `bucket_count` is zero or `bucket_width` is zero.
+    pub fn new(bucket_count: usize, bucket_width: Duration, start: Instant) -> Self {
+        assert!(bucket_count > 0, "window needs at least one bucket");
+        assert!(!bucket_width.is_zero(), "bucket width must be non-zero");
+
+        let mut buckets = VecDeque::with_capacity(bucket_count);
+        buckets.push_back(HashTable::new());
+
+        Self {
+            buckets,
+            bucket_count,
+            bucket_width,
+            newest_start: start,
+        }
+    }
+
+    /// Total time span covered by the window, saturating at
+    /// [`Duration::MAX`].
+    pub fn window(&self) -> Duration {
+        u32::try_from(self.bucket_count)
+            .ok()
+            .and_then(|count| self.bucket_width.checked_mul(count))
+            .unwrap_or(Duration::MAX)
+    }
+
+    /// Rotates buckets so that the newest one contains `now`. Instants
+    /// earlier than the newest bucket are ignored.
+    pub fn advance(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.newest_start);
+        let steps = (elapsed.as_nanos() / self.bucket_width.as_nanos()) as usize;
+        if steps == 0 {
+            return;
+        }
+
+        let steps_u32 = u32::try_from(steps).unwrap_or(u32::MAX);
+        self.newest_start = self
+            .bucket_width
+            .checked_mul(steps_u32)
+            .and_then(|offset| self.newest_start.checked_add(offset))
+            .unwrap_or(now);
+
+        if steps >= self.bucket_count {
+            self.buckets.clear();
+            self.buckets.push_back(HashTable::new());
+            return;
+        }
+
+        for _ in 0..steps {
+            if self.buckets.len() == self.bucket_count {
+                self.buckets.pop_front();
+            }
+            self.buckets.push_back(HashTable::new());
+        }
+    }
+
+    /// Sets `key`'s value in the bucket containing `now`.
+    pub fn insert(&mut self, key: K, value: V, now: Instant) {
+        self.advance(now);
+        self.newest_mut().insert(key, value);
+    }
+
+    /// Updates `key`'s value in the bucket containing `now`, starting from
+    /// `init()` if the key has no value in that bucket yet.
+    pub fn update(
+        &mut self,
+        key: K,
+        now: Instant,
+        init: impl FnOnce() -> V,
+        f: impl FnOnce(&mut V),
+    ) {
+        self.advance(now);
+        let newest = self.newest_mut();
+
+        let index = match newest.find_slot(&key) {
+            Some(index) => index,
+            None => {
+                newest.insert(key.clone(), init());
+                newest.find_slot(&key).unwrap()
+            }
+        };
+        f(newest.slots[index].as_mut().unwrap().value_mut());
+    }
+
+    /// `key`'s value in every live bucket, oldest first.
+    pub fn get_all<'a>(&'a self, key: &'a K) -> impl Iterator<Item = &'a V> + 'a {
+        self.buckets
+            .iter()
+            .filter_map(move |bucket| bucket.get(key))
+    }
+
+    /// Folds `key`'s values across the live buckets, oldest first.
+    pub fn fold<A>(&self, key: &K, init: A, f: impl FnMut(A, &V) -> A) -> A {
+        self.get_all(key).fold(init, f)
+    }
+
+    fn newest_mut(&mut self) -> &mut HashTable<K, V> {
+        self.buckets.back_mut().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn count(table: &WindowedTable<&'static str, u32>, key: &'static str) -> u32 {
+        table.fold(&key, 0, |sum, value| sum + value)
+    }
+
+    #[test]
+    fn test_counts_slide_out_of_window() {
+        let start = Instant::now();
+        let mut table: WindowedTable<&str, u32> = WindowedTable::new(3, 10 * SECOND, start);
+        assert_eq!(table.window(), 30 * SECOND);
+
+        for offset in [0, 5, 12, 25] {
+            table.update("hits", start + offset * SECOND, || 0, |c| *c += 1);
+        }
+        assert_eq!(count(&table, "hits"), 4);
+        assert_eq!(table.get_all(&"hits").count(), 3);
+
+        table.advance(start + 31 * SECOND);
+        assert_eq!(count(&table, "hits"), 2);
+
+        table.advance(start + 45 * SECOND);
+        assert_eq!(count(&table, "hits"), 1);
+
+        table.update("hits", start + 200 * SECOND, || 0, |c| *c += 10);
+        assert_eq!(count(&table, "hits"), 10);
+        assert_eq!(table.buckets.len(), 1);
+    }
+
+    #[test]
+    fn test_insert_overwrites_within_bucket_only() {
+        let start = Instant::now();
+        let mut table: WindowedTable<&str, u32> = WindowedTable::new(2, SECOND, start);
+
+        table.insert("gauge", 1, start);
+        table.insert("gauge", 2, start + SECOND / 2);
+        table.insert("gauge", 3, start + SECOND);
+
+        assert_eq!(
+            table.get_all(&"gauge").copied().collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+
+        table.insert("gauge", 4, start);
+        assert_eq!(
+            table.get_all(&"gauge").copied().collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+    }
+
+    #[cfg(feature = "entry-metadata")]
+    #[test]
+    fn test_update_updates_metadata() {
+        let start = Instant::now();
+        let mut table = WindowedTable::new(3, SECOND, start);
+        table.update("hits", start, || 0, |hits| *hits += 1);
+        let metadata = |table: &WindowedTable<&str, u32>| {
+            table
+                .buckets
+                .back()
+                .unwrap()
+                .entry_metadata(&"hits")
+                .unwrap()
+        };
+        let before = metadata(&table);
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        table.update("hits", start, || 0, |hits| *hits += 1);
+        assert!(metadata(&table).last_modified > before.last_modified);
+    }
+
+    #[test]
+    fn test_window_saturates() {
+        let start = Instant::now();
+        let table: WindowedTable<u32, u32> = WindowedTable::new(4, Duration::from_secs(5), start);
+        assert_eq!(table.window(), Duration::from_secs(20));
+
+        let table: WindowedTable<u32, u32> =
+            WindowedTable::new(2, Duration::from_secs(u64::MAX), start);
+        assert_eq!(table.window(), Duration::MAX);
+    }
+}
@@ -0,0 +1,79 @@
+//! Lookups and removals must not touch the allocator. The shadow model
+//! allocates while checking the table, so these only run without it.
+
+#![cfg(not(feature = "shadow-model"))]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use hash_table::{HashTable, HashTableBuilder, Probing};
+
+/// Counts allocations made by the current thread, so tests running in
+/// parallel do not see each other's.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn check_lookups_do_not_allocate(mut table: HashTable<String, u64>) {
+    for n in 0..1000 {
+        table.insert(format!("key-{n}"), n);
+    }
+    let keys: Vec<String> = (0..2000).map(|n| format!("key-{n}")).collect();
+
+    let allocations = allocations_during(|| {
+        for key in &keys {
+            std::hint::black_box(table.get(key.as_str()));
+            std::hint::black_box(table.contains_key(key.as_str()));
+            if let Some(value) = table.get_mut(key.as_str()) {
+                *value += 1;
+            }
+        }
+        for key in keys.iter().step_by(3) {
+            std::hint::black_box(table.remove(key.as_str()));
+        }
+    });
+
+    assert_eq!(allocations, 0);
+    assert_eq!(table.len(), 1000 - 334);
+}
+
+#[test]
+fn test_lookups_do_not_allocate() {
+    assert_eq!(allocations_during(|| drop(String::from("counted"))), 1);
+    check_lookups_do_not_allocate(HashTable::new());
+}
+
+#[test]
+fn test_two_choice_lookups_do_not_allocate() {
+    let table = HashTableBuilder::new().probing(Probing::TwoChoice).build();
+    check_lookups_do_not_allocate(table);
+}